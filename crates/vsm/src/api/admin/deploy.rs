@@ -16,6 +16,9 @@ use axum::{
 };
 use rand::distributions::{Alphanumeric, DistString};
 
+use vsm_config::Config;
+use vsm_generator::generation;
+
 use crate::AppState;
 
 pub struct DeployState {
@@ -24,28 +27,31 @@ pub struct DeployState {
     server_deployed: AtomicBool,
 }
 
-pub fn initialize(router: Router<Arc<AppState>>) -> (DeployState, Router<Arc<AppState>>) {
+pub fn initialize(
+    router: Router<Arc<AppState>>,
+    config: &Config,
+) -> (DeployState, Router<Arc<AppState>>) {
     (
         DeployState {
-            key: get_key(),
+            key: get_key(&config.server.key_file),
             site_deployed: AtomicBool::new(false),
             server_deployed: AtomicBool::new(false),
         },
         router
             .route("/api/admin/deploy/site", post(site))
-            .route("/api/admin/deploy/server", post(server)),
+            .route("/api/admin/deploy/server", post(server))
+            .route("/api/admin/deploy/rollback", post(rollback)),
     )
 }
 
-fn get_key() -> String {
-    let path = Path::new("deploy.txt");
+fn get_key(path: &Path) -> String {
     if path.exists() {
         return fs::read_to_string(path).expect("Unable to read deploy file");
     }
 
     tracing::info!("Deploy key not found, creating new one.");
     let string = Alphanumeric.sample_string(&mut rand::thread_rng(), 256);
-    fs::write("deploy.txt", string.clone()).expect("Unable to write deploy file");
+    fs::write(path, string.clone()).expect("Unable to write deploy file");
     string
 }
 
@@ -68,7 +74,7 @@ async fn site(State(state): State<Arc<AppState>>, body: String) -> Response {
         Ok(()) => {
             #[cfg(not(debug_assertions))]
             tokio::spawn(async move {
-                crate::run_generator(state.args.clone()).await;
+                crate::run_generator(state.clone()).await;
                 state
                     .api
                     .admin
@@ -106,12 +112,29 @@ async fn site(State(state): State<Arc<AppState>>, body: String) -> Response {
     }
 }
 
+async fn rollback(State(state): State<Arc<AppState>>, body: String) -> Response {
+    if body != state.api.admin.deploy.key {
+        return (StatusCode::FORBIDDEN, "Invalid key").into_response();
+    }
+
+    match state.generator.rollback(&state.config).await {
+        Ok(id) => {
+            state.set_generation(generation::path(&state.config, &id));
+            (StatusCode::OK, format!("Rolled back to generation '{id}'.")).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Rollback failed: {:?}", e);
+            (StatusCode::CONFLICT, format!("Rollback failed: {e}")).into_response()
+        }
+    }
+}
+
 fn deploy_site(state: &Arc<AppState>) -> anyhow::Result<()> {
     tracing::info!("Deploying site");
 
     let child = std::process::Command::new("git")
         .args(vec!["pull", "origin", "master"])
-        .current_dir(Path::new(&state.args.project))
+        .current_dir(&state.config.project)
         .stdout(std::process::Stdio::inherit())
         .spawn()?;
 
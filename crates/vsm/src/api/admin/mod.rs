@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use axum::Router;
 
+use vsm_config::Config;
+
 use crate::AppState;
 
 pub mod deploy;
@@ -10,7 +12,10 @@ pub struct AdminState {
     pub deploy: deploy::DeployState,
 }
 
-pub fn initialize(router: Router<Arc<AppState>>) -> (AdminState, Router<Arc<AppState>>) {
-    let a = deploy::initialize(router);
+pub fn initialize(
+    router: Router<Arc<AppState>>,
+    config: &Config,
+) -> (AdminState, Router<Arc<AppState>>) {
+    let a = deploy::initialize(router, config);
     (AdminState { deploy: a.0 }, a.1)
 }
@@ -2,15 +2,29 @@ use std::sync::Arc;
 
 use axum::Router;
 
+use vsm_config::Config;
+
 use crate::AppState;
 
 pub mod admin;
+pub mod search;
 
 pub struct ApiState {
     pub admin: admin::AdminState,
+    pub search: search::SearchState,
 }
 
-pub fn initialize(router: Router<Arc<AppState>>) -> (ApiState, Router<Arc<AppState>>) {
-    let a = admin::initialize(router);
-    (ApiState { admin: a.0 }, a.1)
+pub fn initialize(
+    router: Router<Arc<AppState>>,
+    config: &Config,
+) -> (ApiState, Router<Arc<AppState>>) {
+    let a = admin::initialize(router, config);
+    let s = search::initialize(a.1, config);
+    (
+        ApiState {
+            admin: a.0,
+            search: s.0,
+        },
+        s.1,
+    )
 }
@@ -0,0 +1,86 @@
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use vsm_search::SearchIndex;
+
+use vsm_config::Config;
+use vsm_generator::generation;
+
+use crate::AppState;
+
+const RESULT_LIMIT: usize = 20;
+
+pub struct SearchState {
+    index: RwLock<SearchIndex>,
+}
+
+impl SearchState {
+    pub fn reload(&self, generation: &Path) {
+        let index = load_index(generation);
+        *self.index.write().unwrap() = index;
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+#[derive(Serialize)]
+struct SearchResponse {
+    title: String,
+    link: String,
+    snippet: String,
+    score: f64,
+}
+
+pub fn initialize(
+    router: Router<Arc<AppState>>,
+    config: &Config,
+) -> (SearchState, Router<Arc<AppState>>) {
+    (
+        SearchState {
+            index: RwLock::new(load_index(&generation::current(config))),
+        },
+        router.route("/api/search", get(search)),
+    )
+}
+
+fn load_index(generation: &Path) -> SearchIndex {
+    let path = generation.join("search.bin");
+    match SearchIndex::load(&path) {
+        Ok(index) => {
+            tracing::info!("Loaded search index with {} documents.", index.len());
+            index
+        }
+        Err(error) => {
+            tracing::warn!("Unable to load search index: {}", error);
+            SearchIndex::new()
+        }
+    }
+}
+
+async fn search(State(state): State<Arc<AppState>>, Query(query): Query<SearchQuery>) -> Response {
+    let index = state.api.search.index.read().unwrap();
+    let results = index
+        .search(&query.q, RESULT_LIMIT)
+        .into_iter()
+        .map(|result| SearchResponse {
+            title: result.document.title.clone(),
+            link: result.document.link.clone(),
+            snippet: result.snippet,
+            score: result.score,
+        })
+        .collect::<Vec<_>>();
+
+    Json(results).into_response()
+}
@@ -2,17 +2,19 @@ use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use tokio::fs;
 
-use crate::{analytics, Args};
+use vsm_config::Config;
+
+use crate::analytics;
 
 pub struct Database {
     pub pool: Pool<SqliteConnectionManager>,
 }
 
 impl Database {
-    pub async fn open(args: &Args) -> anyhow::Result<Self> {
-        fs::create_dir_all(&args.output).await?;
+    pub async fn open(config: &Config) -> anyhow::Result<Self> {
+        fs::create_dir_all(&config.build.output).await?;
 
-        let manager = SqliteConnectionManager::file("database.sqlite3");
+        let manager = SqliteConnectionManager::file(&config.server.database);
         let pool = r2d2::Pool::new(manager)?;
 
         analytics::prepare(pool.get()?).await;
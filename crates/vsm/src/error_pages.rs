@@ -0,0 +1,171 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use vsm_generator::{
+    content::escape_html,
+    routes::{self, SUGGESTIONS_PLACEHOLDER},
+};
+
+use crate::AppState;
+
+const SUGGESTION_LIMIT: usize = 3;
+
+pub async fn not_found(state: &AppState, path: &str) -> Response {
+    let suggestions = suggestions(state, path);
+    let mut list = String::new();
+    for link in &suggestions {
+        list.push_str(&format!(
+            "<li><a href=\"/{0}\">/{0}</a></li>",
+            escape_html(link)
+        ));
+    }
+    if !list.is_empty() {
+        list = format!("<ul class=\"suggestions\">{list}</ul>");
+    }
+
+    render(state, StatusCode::NOT_FOUND, &list).await
+}
+
+pub async fn internal_error(state: &AppState) -> Response {
+    render(state, StatusCode::INTERNAL_SERVER_ERROR, "").await
+}
+
+async fn render(state: &AppState, status: StatusCode, suggestions: &str) -> Response {
+    let path = state
+        .generation()
+        .join("content")
+        .join(status.as_str())
+        .with_extension("html");
+
+    let Ok(page) = tokio::fs::read_to_string(path).await else {
+        let reason = status.canonical_reason().unwrap_or_default();
+        return (status, reason).into_response();
+    };
+
+    #[allow(unused_mut)]
+    let mut body = page
+        .replace(SUGGESTIONS_PLACEHOLDER, suggestions)
+        .into_bytes();
+    #[cfg(debug_assertions)]
+    body.extend_from_slice(crate::HOT_RELOAD_SCRIPT);
+
+    (
+        status,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+fn suggestions(state: &AppState, path: &str) -> Vec<String> {
+    rank(&state.routes().pages, path)
+}
+
+/// Known pages ordered by similarity to the requested path.
+fn rank(pages: &[String], path: &str) -> Vec<String> {
+    let path = routes::normalize(path).to_lowercase();
+    if path.is_empty() {
+        return Vec::new();
+    }
+    let last_segment = path.rsplit('/').next().unwrap_or_default();
+
+    let mut scored = pages
+        .iter()
+        .filter(|link| !link.is_empty())
+        .filter_map(|link| {
+            let lowercase = link.to_lowercase();
+            let link_segment = lowercase.rsplit('/').next().unwrap_or_default();
+            let distance =
+                levenshtein(&path, &lowercase).min(levenshtein(last_segment, link_segment));
+
+            let limit = (path.chars().count() / 3).max(2);
+            (distance <= limit).then_some((distance, link))
+        })
+        .collect::<Vec<_>>();
+
+    scored.sort();
+    scored
+        .into_iter()
+        .take(SUGGESTION_LIMIT)
+        .map(|(_, link)| link.clone())
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let cost = usize::from(a != *b);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages() -> Vec<String> {
+        [
+            "",
+            "about",
+            "blog/rust-tips",
+            "blog/rust-traits",
+            "blog/python",
+            "contact",
+        ]
+        .into_iter()
+        .map(str::to_owned)
+        .collect()
+    }
+
+    #[test]
+    fn ranks_closest_pages_first() {
+        assert_eq!(
+            rank(&pages(), "/blog/rust-tip"),
+            ["blog/rust-tips", "blog/rust-traits"]
+        );
+        assert_eq!(rank(&pages(), "/ABOUT/"), ["about"]);
+    }
+
+    #[test]
+    fn matches_last_segment() {
+        assert_eq!(
+            rank(&pages(), "/posts/rust-traits"),
+            ["blog/rust-traits", "blog/rust-tips"]
+        );
+    }
+
+    #[test]
+    fn skips_distant_pages() {
+        assert!(rank(&pages(), "/completely-unrelated").is_empty());
+        assert!(rank(&pages(), "/").is_empty());
+    }
+
+    #[test]
+    fn limits_suggestions() {
+        let pages = ["a1", "a2", "a3", "a4"].map(str::to_owned);
+        assert_eq!(rank(&pages, "/a"), ["a1", "a2", "a3"]);
+    }
+
+    #[test]
+    fn computes_levenshtein_distance() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("żółw", "zolw"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+}
@@ -2,16 +2,23 @@
 #[macro_use]
 extern crate lazy_static;
 
-use std::{process::Stdio, sync::Arc};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
 use api::ApiState;
 use axum::Router;
-use clap::{command, Parser};
+use clap::Parser;
 use database::Database;
+use vsm_config::Config;
+use vsm_generator::{generation, routes::Routes, Generator};
 
 pub mod analytics;
 pub mod api;
 pub mod database;
+pub mod error_pages;
 pub mod helper;
 pub mod static_files;
 pub mod static_sites;
@@ -55,20 +62,102 @@ static HOT_RELOAD_SCRIPT: &[u8] = br#"
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     /// Path to the project directory
-    #[arg(short, long, default_value = "../vixenka.com")]
-    project: String,
-    /// Path to the output directory
-    #[arg(short, long, default_value = "./output")]
-    output: String,
-    // Page port
-    #[arg(long, default_value = "3000")]
-    port: u16,
+    #[arg(short, long, env = "VSM_PROJECT", default_value = "../vixenka.com")]
+    project: PathBuf,
+    /// Path to the output directory, overrides `build.output`
+    #[arg(short, long, env = "VSM_OUTPUT")]
+    output: Option<PathBuf>,
+    /// Address to listen on, overrides `server.bind`
+    #[arg(long, env = "VSM_BIND")]
+    bind: Option<String>,
+    // Page port, overrides `server.port`
+    #[arg(long, env = "VSM_PORT")]
+    port: Option<u16>,
+    /// Path to the SQLite database, overrides `server.database`
+    #[arg(long, env = "VSM_DATABASE")]
+    database: Option<PathBuf>,
+    /// Path to the deploy key file, overrides `server.key_file`
+    #[arg(long, env = "VSM_KEY_FILE")]
+    key_file: Option<PathBuf>,
+    /// Build profile from the project configuration
+    #[arg(long, env = "VSM_PROFILE")]
+    profile: Option<String>,
 }
 
+impl Args {
+    fn load_config(&self) -> anyhow::Result<Config> {
+        let mut config = Config::load(&self.project, self.profile.as_deref())?;
+        if let Some(output) = &self.output {
+            config.build.output = output.clone();
+        }
+        if let Some(bind) = &self.bind {
+            config.server.bind = bind.clone();
+        }
+        if let Some(port) = self.port {
+            config.server.port = port;
+        }
+        if let Some(database) = &self.database {
+            config.server.database = database.clone();
+        }
+        if let Some(key_file) = &self.key_file {
+            config.server.key_file = key_file.clone();
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// How often the served generation is checked for changes made by other processes.
+const GENERATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct AppState {
-    pub args: Args,
+    pub config: Config,
     pub database: Database,
     pub api: ApiState,
+    pub generator: Generator,
+    /// Directory of the served generation of the website
+    generation: RwLock<PathBuf>,
+    routes: RwLock<Routes>,
+    /// Held while switching generations, so each one is loaded once
+    reload: Mutex<()>,
+}
+
+impl AppState {
+    pub fn generation(&self) -> PathBuf {
+        self.generation.read().unwrap().clone()
+    }
+
+    /// Loads the search index and routes of the generation and serves it, unless it is served
+    /// already.
+    pub fn set_generation(&self, path: PathBuf) {
+        let _guard = self.reload.lock().unwrap();
+        if *self.generation.read().unwrap() == path {
+            return;
+        }
+
+        tracing::info!("Serving generation '{}'.", path.display());
+        self.api.search.reload(&path);
+        *self.routes.write().unwrap() = load_routes(&path);
+        *self.generation.write().unwrap() = path;
+    }
+
+    pub fn routes(&self) -> std::sync::RwLockReadGuard<'_, Routes> {
+        self.routes.read().unwrap()
+    }
+}
+
+fn load_routes(generation: &std::path::Path) -> Routes {
+    match Routes::load(generation) {
+        Ok(routes) => {
+            tracing::info!("Loaded {} redirects.", routes.redirects.len());
+            routes
+        }
+        Err(error) => {
+            tracing::warn!("Unable to load routes: {}", error);
+            Routes::default()
+        }
+    }
 }
 
 #[tokio::main]
@@ -76,31 +165,57 @@ async fn main() {
     tracing_subscriber::fmt::init();
 
     let args = Args::parse();
-    let generator = tokio::spawn(run_generator(args.clone()));
+    let config = match args.load_config() {
+        Ok(config) => config,
+        Err(err) => {
+            tracing::error!("Invalid configuration: {:#}", err);
+            std::process::exit(1);
+        }
+    };
 
-    let database = Database::open(&args)
+    let database = Database::open(&config)
         .await
         .expect("Failed to open database.");
 
+    let generator = match Generator::new(&config) {
+        Ok(generator) => generator,
+        Err(err) => {
+            tracing::error!("Unable to create generator: {:#}", err);
+            std::process::exit(1);
+        }
+    };
+
     let router = static_files::initialize(static_sites::initialize(Router::new()));
-    let (api, router) = api::initialize(router);
+    let (api, router) = api::initialize(router, &config);
 
-    #[allow(unused_mut)]
-    let mut router = router.with_state(Arc::new(AppState {
-        args: args.clone(),
+    let address = format!("{}:{}", config.server.bind, config.server.port);
+    let current_generation = generation::current(&config);
+    let port = config.server.port;
+
+    let state = Arc::new(AppState {
+        config,
         database,
         api,
-    }));
+        generator,
+        routes: RwLock::new(load_routes(&current_generation)),
+        generation: RwLock::new(current_generation),
+        reload: Mutex::new(()),
+    });
+    let generator = tokio::spawn(run_generator(state.clone()));
+    tokio::spawn(watch_generation(state.clone()));
+
+    #[allow(unused_mut)]
+    let mut router = router.with_state(state);
 
     #[cfg(debug_assertions)]
     {
         router = router.route("/ws/hotreload", axum::routing::get(hot_reload_handler))
     }
 
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", args.port))
+    let listener = tokio::net::TcpListener::bind(address)
         .await
         .expect("Unable to start listener.");
-    tracing::info!("Serve website on http://localhost:{}", args.port);
+    tracing::info!("Serve website on http://localhost:{}", port);
 
     axum::serve(listener, router.into_make_service())
         .await
@@ -110,73 +225,86 @@ async fn main() {
     drop(generator.await);
 }
 
+/// Generations can also be activated by the standalone generator, so the current one is polled and
+/// switched to when it changed.
+async fn watch_generation(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(GENERATION_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let state = state.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let current = generation::current(&state.config);
+            state.set_generation(current);
+        })
+        .await;
+        if let Err(error) = result {
+            tracing::error!("Unable to switch generation: {}", error);
+        }
+    }
+}
+
 #[cfg(not(debug_assertions))]
 #[allow(clippy::unused_unit)]
-pub async fn run_generator(args: Args) -> () {
-    run_generator_impl(&args)
+pub async fn run_generator(state: Arc<AppState>) -> () {
+    run_generator_impl(&state).await
 }
 
 #[cfg(debug_assertions)]
-async fn run_generator(args: Args) -> notify::ReadDirectoryChangesWatcher {
+async fn run_generator(state: Arc<AppState>) -> notify::RecommendedWatcher {
     use notify::Watcher;
 
-    run_generator_impl(&args);
+    run_generator_impl(&state).await;
 
-    let mut watcher = notify::recommended_watcher(|res| match res {
+    let handle = tokio::runtime::Handle::current();
+    let watcher_state = state.clone();
+    let mut watcher = notify::recommended_watcher(move |res| match res {
         Ok(_) => {
-            let args = Args::parse();
-            run_generator_impl(&args);
+            handle.block_on(run_generator_impl(&watcher_state));
             _ = HOT_RELOAD.send(());
         }
         Err(e) => tracing::error!("Watch error: {:?}", e),
     })
     .unwrap();
     watcher
-        .watch(
-            std::path::Path::new(&args.project),
-            notify::RecursiveMode::Recursive,
-        )
+        .watch(&state.config.project, notify::RecursiveMode::Recursive)
         .unwrap();
     watcher
 }
 
-fn run_generator_impl(args: &Args) {
-    tracing::info!("Running generator for project: {}", args.project);
-
-    let executable;
-    let mut command_args = Vec::new();
-    #[cfg(not(feature = "deploy"))]
-    {
-        executable = "cargo";
-        command_args.extend_from_slice(&["run", "--bin", "vsm_generator"]);
-
-        #[cfg(not(debug_assertions))]
-        {
-            command_args.push("--release");
+async fn run_generator_impl(state: &AppState) {
+    let config = &state.config;
+    tracing::info!(
+        "Running generator for project: {}",
+        config.project.display()
+    );
+
+    let report = match state.generator.generate(config).await {
+        Ok(report) => report,
+        Err(err) => {
+            tracing::error!("Generator failed: {:#}", err);
+            return;
         }
+    };
 
-        command_args.push("--");
+    for error in report.result.errors() {
+        tracing::error!("{}", error);
     }
-    #[cfg(feature = "deploy")]
-    {
-        executable = "./vsm_generator";
+    for warning in report.result.warnings() {
+        tracing::warn!("{}", warning);
     }
-    command_args.extend_from_slice(&["--project", &args.project, "--output", &args.output]);
-
-    let child = std::process::Command::new(executable)
-        .args(command_args.as_slice())
-        .stdout(Stdio::inherit())
-        .spawn()
-        .expect("Failed to execute process");
 
-    let output = child
-        .wait_with_output()
-        .expect("Failed to wait for process");
-
-    if !output.status.success() {
-        tracing::error!("Generator failed: {:?}", output.status);
+    if !report.is_success() {
+        tracing::error!(
+            "Generator finished with {} errors in {:.2?}",
+            report.result.errors().len(),
+            report.duration
+        );
     } else {
-        tracing::info!("Generator finished");
+        tracing::info!("Generator finished in {:.2?}", report.duration);
+    }
+    if let Some(id) = report.generation {
+        state.set_generation(generation::path(config, &id));
     }
 }
 
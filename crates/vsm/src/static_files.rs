@@ -12,7 +12,7 @@ use axum::{
 use mime_guess::mime;
 use tokio::fs;
 
-use crate::{analytics, helper, AppState};
+use crate::{analytics, error_pages, helper, AppState};
 
 pub fn initialize(router: Router<Arc<AppState>>) -> Router<Arc<AppState>> {
     router.route("/static/*path", get(serve))
@@ -23,7 +23,7 @@ async fn serve(
     Path(path): Path<String>,
     request: Request<Body>,
 ) -> Response {
-    let mut file_path = std::path::Path::new("./output/static").join(&path);
+    let mut file_path = state.generation().join("static").join(&path);
 
     let mime = match mime_guess::from_path(&file_path).first() {
         Some(mime) => mime,
@@ -42,15 +42,21 @@ async fn serve(
         ));
     }
 
-    let file_content = fs::read(&file_path);
-
-    tokio::spawn(analytics::push(state, path.clone(), request));
-
-    match file_content.await {
+    let file_content = fs::read(&file_path).await;
+    let response = match file_content {
         #[allow(unused_mut)]
         Ok(mut content) => serve_data(accept_gzip, content, &mime),
-        Err(_) => error_404(&path),
-    }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            error_pages::not_found(&state, &path).await
+        }
+        Err(error) => {
+            tracing::error!("Unable to read static file '{}': {}", path, error);
+            error_pages::internal_error(&state).await
+        }
+    };
+
+    tokio::spawn(analytics::push(state, path.clone(), request));
+    response
 }
 
 fn serve_data(accept_gzip: bool, content: Vec<u8>, mime: &str) -> Response {
@@ -82,7 +88,3 @@ fn serve_data(accept_gzip: bool, content: Vec<u8>, mime: &str) -> Response {
             .into_response(),
     }
 }
-
-fn error_404(path: &str) -> Response {
-    (StatusCode::NOT_FOUND, format!("Not found {path}")).into_response()
-}
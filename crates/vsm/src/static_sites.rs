@@ -10,8 +10,9 @@ use axum::{
 };
 
 use tokio::fs;
+use vsm_generator::routes;
 
-use crate::{analytics, helper, AppState};
+use crate::{analytics, error_pages, helper, AppState};
 
 pub fn initialize(router: Router<Arc<AppState>>) -> Router<Arc<AppState>> {
     router.route("/", get(root)).route("/*path", get(tree))
@@ -30,10 +31,11 @@ async fn tree(
 }
 
 async fn serve_impl(state: Arc<AppState>, path: String, request: Request<Body>) -> Response {
-    let mut file_path = std::path::Path::new("./output/content").join(&path);
-    if file_path.extension().is_some() {
+    let mut file_path = state.generation().join("content").join(&path);
+    if file_path.extension().is_some() || routes::is_error_page(&path) {
+        let response = not_found(&state, &path).await;
         tokio::spawn(analytics::push(state, path.clone(), request));
-        return error_404(&path);
+        return response;
     }
 
     let accept_gzip = helper::accept_gzip(&request);
@@ -42,19 +44,25 @@ async fn serve_impl(state: Arc<AppState>, path: String, request: Request<Body>)
         false => "html",
     });
 
-    let file_content = fs::read(file_path);
-
-    tokio::spawn(analytics::push(state, path.clone(), request));
-
-    match file_content.await {
+    let file_content = fs::read(file_path).await;
+    let response = match file_content {
         #[allow(unused_mut)]
         Ok(mut content) => {
             #[cfg(debug_assertions)]
             content.extend_from_slice(crate::HOT_RELOAD_SCRIPT);
             serve_data(accept_gzip, content)
         }
-        Err(_) => error_404(&path),
-    }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            not_found(&state, &path).await
+        }
+        Err(error) => {
+            tracing::error!("Unable to read page '{}': {}", path, error);
+            error_pages::internal_error(&state).await
+        }
+    };
+
+    tokio::spawn(analytics::push(state, path.clone(), request));
+    response
 }
 
 fn serve_data(accept_gzip: bool, content: Vec<u8>) -> Response {
@@ -86,6 +94,19 @@ fn serve_data(accept_gzip: bool, content: Vec<u8>) -> Response {
     }
 }
 
-fn error_404(path: &str) -> Response {
-    (StatusCode::NOT_FOUND, format!("Not found {path}")).into_response()
+async fn not_found(state: &AppState, path: &str) -> Response {
+    let Some(redirect) = state.routes().redirect(path).cloned() else {
+        return error_pages::not_found(state, path).await;
+    };
+
+    match (
+        StatusCode::from_u16(redirect.status),
+        HeaderValue::from_str(&redirect.to),
+    ) {
+        (Ok(status), Ok(location)) => (status, [(header::LOCATION, location)]).into_response(),
+        _ => {
+            tracing::error!("Invalid redirect from '{}': {:?}", path, redirect);
+            error_pages::internal_error(state).await
+        }
+    }
 }
@@ -0,0 +1,696 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+use toml::value::{Datetime, Offset};
+use url::Url;
+
+pub const CONFIG_FILE: &str = "vsm.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip)]
+    pub project: PathBuf,
+    pub site: SiteConfig,
+    pub build: BuildConfig,
+    pub server: ServerConfig,
+    pub markdown: MarkdownConfig,
+    /// Keyed by collection name, available in templates as `{{collection.<name>}}`
+    pub collections: BTreeMap<String, CollectionConfig>,
+    pub related: RelatedConfig,
+    pub reading: ReadingConfig,
+    /// Front matter of markdown pages, merged with [`SchemaConfig::builtin`] fields
+    pub schema: SchemaConfig,
+    pub profiles: HashMap<String, ProfileConfig>,
+    /// Name of the applied profile
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    pub title: String,
+    pub base_url: String,
+    pub author: String,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            title: "Vixenka".to_owned(),
+            base_url: "https://vixenka.com".to_owned(),
+            author: "Vixenka".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildConfig {
+    /// Relative to the working directory, not to the project
+    pub output: PathBuf,
+    pub minify: bool,
+    /// Environment variables available in templates as `{{env.<name>}}`
+    pub env: Vec<String>,
+    /// By default a build with any error is discarded and the previous generation stays served.
+    /// When enabled, it is activated anyway and pages which failed keep their previous version.
+    pub activate_with_errors: bool,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            output: PathBuf::from("./output"),
+            minify: !cfg!(debug_assertions),
+            env: Vec::new(),
+            activate_with_errors: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub port: u16,
+    pub database: PathBuf,
+    pub key_file: PathBuf,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0".to_owned(),
+            port: 3000,
+            database: PathBuf::from("database.sqlite3"),
+            key_file: PathBuf::from("deploy.txt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownConfig {
+    pub tables: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    pub tasklists: bool,
+    pub smart_punctuation: bool,
+    pub heading_attributes: bool,
+}
+
+/// Collection used by `{{md_post_list}}`, includes all markdown pages unless configured otherwise.
+pub const DEFAULT_COLLECTION: &str = "posts";
+
+/// Markdown pages are included when they list the collection in `collections` front matter, or
+/// when they match both `directory` and `tags` filters if at least one of them is set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectionConfig {
+    /// Relative to the content directory, includes subdirectories
+    pub directory: Option<PathBuf>,
+    /// Pages must have at least one of the tags
+    pub tags: Vec<String>,
+    pub sort_by: SortBy,
+    pub order: SortOrder,
+    pub include_drafts: bool,
+    pub limit: Option<usize>,
+    /// Additional front matter of pages in the collection, overrides the project schema
+    pub schema: SchemaConfig,
+    /// Splits the list into pages available at `<link>/page/<number>`
+    pub per_page: Option<usize>,
+    /// Shown instead of the list when the collection is empty
+    pub empty: Option<String>,
+    /// Name of the template rendering a single page of the list, with `{{link}}`, `{{title}}`,
+    /// `{{description}}`, `{{excerpt}}`, `{{date}}`, `{{image}}` and `{{tags}}` variables
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    Date,
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemaConfig {
+    pub fields: BTreeMap<String, FieldConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldConfig {
+    #[serde(rename = "type")]
+    pub kind: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    /// Used when the field is missing, dates are written as TOML dates or RFC 3339 strings
+    pub default: Option<FieldValue>,
+    /// Allowed values, for arrays allowed values of their elements
+    #[serde(default)]
+    pub values: Vec<FieldValue>,
+}
+
+impl FieldConfig {
+    fn optional(kind: FieldType, default: Option<FieldValue>) -> Self {
+        Self {
+            kind,
+            required: false,
+            default,
+            values: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    String,
+    Number,
+    Bool,
+    Date,
+    /// Array of strings
+    Array,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Bool => "bool",
+            Self::Date => "date",
+            Self::Array => "array",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Date(Datetime),
+    Array(Vec<FieldValue>),
+}
+
+impl FieldValue {
+    /// Checks that the value is of the given type, elements of arrays must be strings.
+    pub fn check(&self, kind: FieldType) -> anyhow::Result<()> {
+        match (kind, self) {
+            (FieldType::String, FieldValue::String(_))
+            | (FieldType::Number, FieldValue::Number(_))
+            | (FieldType::Bool, FieldValue::Bool(_)) => Ok(()),
+            (FieldType::Date, value) => value.to_date().map(|_| ()),
+            (FieldType::Array, FieldValue::Array(array)) => array
+                .iter()
+                .try_for_each(|value| value.check(FieldType::String)),
+            _ => anyhow::bail!("expected value of type '{}'", kind),
+        }
+    }
+
+    /// Dates without time are at midnight and ones without offset are in UTC.
+    pub fn to_date(&self) -> anyhow::Result<DateTime<Utc>> {
+        let datetime = match self {
+            FieldValue::String(str) => {
+                return str.parse().with_context(|| {
+                    format!(
+                        "'{}' is not an RFC 3339 date, e.g. 2024-01-31T12:00:00Z",
+                        str
+                    )
+                })
+            }
+            FieldValue::Date(datetime) => datetime,
+            _ => anyhow::bail!("expected value of type 'date'"),
+        };
+
+        let Some(date) = datetime.date else {
+            anyhow::bail!("'{}' is a time without a date", datetime);
+        };
+        let date = NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into());
+        let time = match datetime.time {
+            Some(time) => NaiveTime::from_hms_nano_opt(
+                time.hour.into(),
+                time.minute.into(),
+                time.second.into(),
+                time.nanosecond,
+            ),
+            None => Some(NaiveTime::MIN),
+        };
+        let (Some(date), Some(time)) = (date, time) else {
+            anyhow::bail!("'{}' is not a valid date", datetime);
+        };
+
+        let offset = match datetime.offset {
+            Some(Offset::Custom { minutes }) => FixedOffset::east_opt(i32::from(minutes) * 60),
+            Some(Offset::Z) | None => FixedOffset::east_opt(0),
+        };
+        let Some(offset) = offset else {
+            anyhow::bail!("'{}' has an invalid offset", datetime);
+        };
+        match offset.from_local_datetime(&date.and_time(time)).single() {
+            Some(datetime) => Ok(datetime.with_timezone(&Utc)),
+            None => anyhow::bail!("'{}' is not a valid date", datetime),
+        }
+    }
+}
+
+impl SchemaConfig {
+    /// Fields used by the generator itself, their types can not be changed.
+    pub fn builtin() -> Self {
+        let required = |kind| FieldConfig {
+            required: true,
+            ..FieldConfig::optional(kind, None)
+        };
+
+        let fields = [
+            ("title", required(FieldType::String)),
+            (
+                "description",
+                FieldConfig::optional(FieldType::String, None),
+            ),
+            ("date", required(FieldType::Date)),
+            (
+                "tags",
+                FieldConfig::optional(FieldType::Array, Some(FieldValue::Array(Vec::new()))),
+            ),
+            (
+                "draft",
+                FieldConfig::optional(FieldType::Bool, Some(FieldValue::Bool(false))),
+            ),
+            (
+                "technical",
+                FieldConfig::optional(FieldType::Bool, Some(FieldValue::Bool(false))),
+            ),
+            (
+                "difficulty",
+                FieldConfig::optional(FieldType::Number, Some(FieldValue::Number(0.0))),
+            ),
+            ("image", FieldConfig::optional(FieldType::String, None)),
+            ("aliases", FieldConfig::optional(FieldType::Array, None)),
+            ("slug", FieldConfig::optional(FieldType::String, None)),
+            ("permalink", FieldConfig::optional(FieldType::String, None)),
+            ("collections", FieldConfig::optional(FieldType::Array, None)),
+            ("series", FieldConfig::optional(FieldType::String, None)),
+            (
+                "series_order",
+                FieldConfig::optional(FieldType::Number, None),
+            ),
+            ("related", FieldConfig::optional(FieldType::Array, None)),
+            ("updated", FieldConfig::optional(FieldType::Date, None)),
+            ("lang", FieldConfig::optional(FieldType::String, None)),
+            ("author", FieldConfig::optional(FieldType::String, None)),
+            ("authors", FieldConfig::optional(FieldType::Array, None)),
+        ];
+
+        Self {
+            fields: fields
+                .into_iter()
+                .map(|(key, field)| (key.to_owned(), field))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RelatedConfig {
+    /// Maximal number of related posts, including pinned ones
+    pub count: usize,
+    /// Name of the template rendering a single related post, with the same variables as
+    /// [`CollectionConfig::template`], a list of links is rendered when the template does not exist
+    pub template: String,
+}
+
+impl Default for RelatedConfig {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            template: "related-post".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReadingConfig {
+    /// Language of pages without `lang` front matter which are not detected as Polish
+    pub language: String,
+    /// Reading speed of prose keyed by language codes
+    pub words_per_minute: BTreeMap<String, f64>,
+    /// Replaces `difficulty` of pages which do not set it with one computed from readability
+    pub auto_difficulty: bool,
+}
+
+impl Default for ReadingConfig {
+    fn default() -> Self {
+        Self {
+            language: "en".to_owned(),
+            words_per_minute: BTreeMap::from([("en".to_owned(), 240.0), ("pl".to_owned(), 200.0)]),
+            auto_difficulty: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    pub base_url: Option<String>,
+    pub output: Option<PathBuf>,
+    pub minify: Option<bool>,
+}
+
+impl Config {
+    pub fn load(project: &Path, profile: Option<&str>) -> anyhow::Result<Self> {
+        if !project.is_dir() {
+            anyhow::bail!("Project directory '{}' does not exist.", project.display());
+        }
+
+        let path = project.join(CONFIG_FILE);
+        let mut config = match fs::read_to_string(&path) {
+            Ok(data) => toml::from_str::<Config>(&data)
+                .with_context(|| format!("Unable to parse '{}'", path.display()))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!(
+                    "Configuration file '{}' not found, using defaults.",
+                    path.display()
+                );
+                Config::default()
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("Unable to read '{}'", path.display()))
+            }
+        };
+        config.project = project.to_path_buf();
+        config
+            .collections
+            .entry(DEFAULT_COLLECTION.to_owned())
+            .or_insert_with(|| CollectionConfig {
+                directory: Some(PathBuf::new()),
+                ..Default::default()
+            });
+
+        let mut schema = SchemaConfig::builtin();
+        schema.fields.append(&mut config.schema.fields);
+        config.schema = schema;
+
+        if let Some(profile) = profile {
+            config.apply_profile(profile)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn project_content(&self) -> PathBuf {
+        self.project.join("content")
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.site.title.trim().is_empty() {
+            anyhow::bail!("'site.title' must not be empty.");
+        }
+
+        match Url::parse(&self.site.base_url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => (),
+            Ok(url) => anyhow::bail!(
+                "'site.base_url' must use http or https scheme, found '{}'.",
+                url.scheme()
+            ),
+            Err(error) => anyhow::bail!(
+                "'site.base_url' value '{}' is not a valid URL: {}.",
+                self.site.base_url,
+                error
+            ),
+        }
+
+        if self.server.bind.parse::<IpAddr>().is_err() {
+            anyhow::bail!(
+                "'server.bind' value '{}' is not a valid IP address.",
+                self.server.bind
+            );
+        }
+
+        for (name, collection) in &self.collections {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                anyhow::bail!(
+                    "Collection name '{}' may only contain ASCII letters, digits, '_' and '-'.",
+                    name
+                );
+            }
+            if let Some(directory) = &collection.directory {
+                if directory.is_absolute() || directory.components().any(|c| c.as_os_str() == "..")
+                {
+                    anyhow::bail!(
+                        "'collections.{}.directory' must be relative to the content directory.",
+                        name
+                    );
+                }
+            }
+        }
+
+        for (language, words_per_minute) in &self.reading.words_per_minute {
+            if *words_per_minute <= 0.0 {
+                anyhow::bail!(
+                    "'reading.words_per_minute.{}' must be greater than 0.",
+                    language
+                );
+            }
+        }
+
+        let builtin = SchemaConfig::builtin();
+        let schemas = std::iter::once(("schema".to_owned(), &self.schema)).chain(
+            self.collections.iter().map(|(name, collection)| {
+                (format!("collections.{}.schema", name), &collection.schema)
+            }),
+        );
+        for (prefix, schema) in schemas {
+            for (key, field) in &schema.fields {
+                if let Some(builtin) = builtin.fields.get(key) {
+                    if builtin.kind != field.kind {
+                        anyhow::bail!(
+                            "'{}.fields.{}.type' must be '{}' for the built-in field.",
+                            prefix,
+                            key,
+                            builtin.kind
+                        );
+                    }
+                    if builtin.required && !field.required && field.default.is_none() {
+                        anyhow::bail!(
+                            "'{}.fields.{}' must be required or have a default value.",
+                            prefix,
+                            key
+                        );
+                    }
+                }
+                if field.required && field.default.is_some() {
+                    anyhow::bail!(
+                        "'{}.fields.{}' can not be required and have a default value.",
+                        prefix,
+                        key
+                    );
+                }
+                if let Some(Err(error)) = field.default.as_ref().map(|d| d.check(field.kind)) {
+                    anyhow::bail!(
+                        "'{}.fields.{}.default' is invalid: {:#}.",
+                        prefix,
+                        key,
+                        error
+                    );
+                }
+
+                let element_kind = match field.kind {
+                    FieldType::Array => FieldType::String,
+                    kind => kind,
+                };
+                for value in &field.values {
+                    if let Err(error) = value.check(element_kind) {
+                        anyhow::bail!(
+                            "'{}.fields.{}.values' is invalid: {:#}.",
+                            prefix,
+                            key,
+                            error
+                        );
+                    }
+                }
+            }
+        }
+
+        let current_dir = std::env::current_dir()?;
+        let output = normalize(&current_dir.join(&self.build.output));
+        let project = normalize(&current_dir.join(&self.project));
+        if output.starts_with(&project) {
+            anyhow::bail!(
+                "'build.output' directory '{}' must not be inside the project directory '{}'.",
+                self.build.output.display(),
+                self.project.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn apply_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let mut available = self.profiles.keys().cloned().collect::<Vec<_>>();
+            available.sort();
+            anyhow::bail!(
+                "Profile '{}' is not defined in '{}'. Available profiles: [{}].",
+                name,
+                CONFIG_FILE,
+                available.join(", ")
+            );
+        };
+
+        if let Some(base_url) = profile.base_url {
+            self.site.base_url = base_url;
+        }
+        if let Some(output) = profile.output {
+            self.build.output = output;
+        }
+        if let Some(minify) = profile.minify {
+            self.build.minify = minify;
+        }
+
+        self.profile = Some(name.to_owned());
+        tracing::info!("Using build profile '{}'.", name);
+        Ok(())
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => (),
+            std::path::Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &str) -> Config {
+        let mut config = toml::from_str::<Config>(data).unwrap();
+        config.project = PathBuf::from("/nonexistent/project");
+        config
+    }
+
+    fn validate_error(data: &str) -> String {
+        format!("{:#}", parse(data).validate().unwrap_err())
+    }
+
+    #[test]
+    fn applies_profile_overrides() {
+        let mut config = parse(
+            r#"
+            [site]
+            base_url = "https://example.com"
+
+            [build]
+            output = "./output"
+            minify = false
+
+            [profiles.staging]
+            base_url = "https://staging.example.com"
+            minify = true
+            "#,
+        );
+
+        config.apply_profile("staging").unwrap();
+        assert_eq!(config.site.base_url, "https://staging.example.com");
+        assert_eq!(config.build.output, PathBuf::from("./output"));
+        assert!(config.build.minify);
+        assert_eq!(config.profile.as_deref(), Some("staging"));
+    }
+
+    #[test]
+    fn rejects_unknown_profile() {
+        let mut config = parse(
+            r#"
+            [profiles.staging]
+            [profiles.local]
+            "#,
+        );
+
+        let error = config.apply_profile("production").unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Profile 'production' is not defined in 'vsm.toml'. Available profiles: [local, staging]."
+        );
+        assert_eq!(config.profile, None);
+    }
+
+    #[test]
+    fn accepts_valid_config() {
+        parse("").validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        assert_eq!(
+            validate_error("[site]\ntitle = \" \""),
+            "'site.title' must not be empty."
+        );
+        assert_eq!(
+            validate_error("[site]\nbase_url = \"ftp://example.com\""),
+            "'site.base_url' must use http or https scheme, found 'ftp'."
+        );
+        assert_eq!(
+            validate_error("[server]\nbind = \"localhost\""),
+            "'server.bind' value 'localhost' is not a valid IP address."
+        );
+        assert_eq!(
+            validate_error("[collections.\"my posts\"]"),
+            "Collection name 'my posts' may only contain ASCII letters, digits, '_' and '-'."
+        );
+        assert_eq!(
+            validate_error("[collections.posts]\ndirectory = \"../outside\""),
+            "'collections.posts.directory' must be relative to the content directory."
+        );
+        assert_eq!(
+            validate_error("[reading.words_per_minute]\nen = 0"),
+            "'reading.words_per_minute.en' must be greater than 0."
+        );
+        assert_eq!(
+            validate_error("[build]\noutput = \"/nonexistent/project/output\""),
+            "'build.output' directory '/nonexistent/project/output' must not be inside the project \
+             directory '/nonexistent/project'."
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(toml::from_str::<Config>("[site]\nname = \"Blog\"").is_err());
+    }
+}
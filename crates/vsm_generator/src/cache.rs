@@ -12,6 +12,7 @@ use serde::{Deserialize, Serialize};
 pub struct Cache {
     path: PathBuf,
     inner: CacheInner,
+    modified: bool,
 }
 
 impl Cache {
@@ -24,6 +25,7 @@ impl Cache {
                     return Ok(Self {
                         path,
                         inner: CacheInner::new(),
+                        modified: false,
                     });
                 }
 
@@ -46,37 +48,55 @@ impl Cache {
             }
         };
 
-        Ok(Self { path, inner })
+        Ok(Self {
+            path,
+            inner,
+            modified: false,
+        })
     }
-}
 
-impl Drop for Cache {
-    #[tracing::instrument]
-    fn drop(&mut self) {
+    #[tracing::instrument(skip(self))]
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        if !self.modified {
+            return Ok(());
+        }
+
         let mut serializer = flexbuffers::FlexbufferSerializer::new();
-        self.inner
-            .serialize(&mut serializer)
-            .expect("Unable to serialize cache");
-
-        fs::create_dir_all(
-            self.path
-                .parent()
-                .expect("Unable to get parent of cache file"),
-        )
-        .expect("Unable to create cache directory");
-
-        File::create(&self.path)
-            .unwrap()
-            .write_all(serializer.view())
-            .expect("Unable to save cache file");
+        self.inner.serialize(&mut serializer)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        File::create(&self.path)?.write_all(serializer.view())?;
 
+        self.modified = false;
         tracing::trace!("Saved cache file.");
+        Ok(())
+    }
+
+    pub fn social_card_hash(&self, name: &str) -> Option<&[u8; 32]> {
+        self.inner.social_cards.get(name)
+    }
+
+    pub fn set_social_card_hash(&mut self, name: String, hash: [u8; 32]) {
+        self.inner.social_cards.insert(name, hash);
+        self.modified = true;
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if let Err(error) = self.save() {
+            tracing::error!("Unable to save cache: {}", error);
+        }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CacheInner {
     contents: HashMap<[u8; 32], ContentCache>,
+    #[serde(default)]
+    social_cards: HashMap<String, [u8; 32]>,
 }
 
 impl CacheInner {
@@ -85,6 +105,7 @@ impl CacheInner {
         tracing::trace!("Creating default cache.");
         Self {
             contents: HashMap::new(),
+            social_cards: HashMap::new(),
         }
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     io::{Cursor, Write},
     path::{Path, PathBuf},
     sync::Arc,
@@ -11,20 +12,36 @@ use quick_xml::{
     Reader,
 };
 use serde::{Deserialize, Serialize};
-use tokio::{
-    fs,
-    io::{AsyncReadExt, AsyncWriteExt},
-    task::JoinSet,
-};
+use tokio::{io::AsyncReadExt, task::JoinSet};
 use walkdir::WalkDir;
 
-use crate::{content::content_variables::ContentVariables, Context};
+use crate::{
+    content::content_variables::ContentVariables,
+    diagnostic::{self, Diagnostic, Severity},
+    output, Context, Mode,
+};
 
 use self::preliminary_analysis::PreliminaryAnalysisOutput;
 
+pub mod archive;
+pub mod authors;
+pub mod build_metadata;
+pub mod collections;
 pub mod content_variables;
+pub mod data;
+pub mod history;
+pub mod links;
 pub mod markdown;
+pub mod meta;
+pub mod permalink;
 pub mod preliminary_analysis;
+pub mod redirects;
+pub mod related;
+pub mod schema;
+pub mod search;
+pub mod series;
+pub mod social_card;
+pub mod structured_data;
 pub mod word_counter;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,8 +49,8 @@ pub struct ContentCache {}
 
 #[derive(Debug, Default)]
 pub struct ContentResult {
-    errors: Vec<anyhow::Error>,
-    warnings: Vec<anyhow::Error>,
+    errors: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>,
 }
 
 impl ContentResult {
@@ -44,28 +61,77 @@ impl ContentResult {
         }
     }
 
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        let diagnostics = match diagnostic.is_error() {
+            true => &mut self.errors,
+            false => &mut self.warnings,
+        };
+
+        // Variables are applied again after each template insertion, so the same problem can be
+        // reported more than once for the same location.
+        if !diagnostics.iter().any(|d| {
+            d.code == diagnostic.code
+                && d.message == diagnostic.message
+                && d.file == diagnostic.file
+                && d.line == diagnostic.line
+                && d.column == diagnostic.column
+        }) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
     pub fn push_error(&mut self, error: anyhow::Error) {
-        self.errors.push(error);
+        self.push(error.into());
     }
 
     pub fn push_warning(&mut self, warning: anyhow::Error) {
-        self.warnings.push(warning);
+        let mut diagnostic = Diagnostic::from(warning);
+        diagnostic.severity = Severity::Warning;
+        self.push(diagnostic);
+    }
+
+    pub fn extend(&mut self, other: ContentResult) {
+        for diagnostic in other.errors.into_iter().chain(other.warnings) {
+            self.push(diagnostic);
+        }
     }
 
-    pub fn errors(&self) -> &[anyhow::Error] {
+    /// Points diagnostics without location at the first of the files where their source was found.
+    pub fn locate(&mut self, files: &[&Path]) {
+        for diagnostic in self.errors.iter_mut().chain(self.warnings.iter_mut()) {
+            if !diagnostic.is_located() {
+                diagnostic.locate(files.iter().copied());
+            }
+        }
+    }
+
+    pub fn errors(&self) -> &[Diagnostic] {
         &self.errors
     }
 
-    pub fn warnings(&self) -> &[anyhow::Error] {
+    pub fn warnings(&self) -> &[Diagnostic] {
         &self.warnings
     }
+
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.errors.iter().chain(self.warnings.iter())
+    }
 }
 
 pub async fn process_content(context: &Arc<Context>) -> anyhow::Result<ContentResult> {
     let mut set = JoinSet::new();
-    for file in collect_files_for_processing(&Path::new(&context.args.project).join("content")) {
+    for file in collect_files_for_processing(&Path::new(&context.config.project).join("content")) {
         let context = context.clone();
-        set.spawn(async move { preliminary_analysis::analyze_file(context, file).await });
+        set.spawn(async move {
+            preliminary_analysis::analyze_file(context, file.clone())
+                .await
+                .map_err(|error| {
+                    diagnostic::collect(error)
+                        .into_iter()
+                        .map(|diagnostic| diagnostic.or_file(&file))
+                        .collect::<Vec<_>>()
+                })
+        });
     }
 
     let mut content_result = ContentResult::new();
@@ -81,24 +147,65 @@ pub async fn process_content(context: &Arc<Context>) -> anyhow::Result<ContentRe
 
         match result {
             Ok(previous_step) => preliminary_outputs.push(Arc::new(previous_step)),
-            Err(error) => {
-                content_result.push_error(error);
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    content_result.push(diagnostic);
+                }
                 continue;
             }
         }
     }
 
-    let md_post_list = preliminary_analysis::create_md_post_list(&preliminary_outputs).await?;
+    check_duplicate_links(&mut preliminary_outputs, &mut content_result);
+
+    let mut collections =
+        collections::create_collections(context, &preliminary_outputs, &mut content_result);
+    preliminary_outputs.extend(collections::create_tag_pages(
+        context,
+        &preliminary_outputs,
+        &mut collections,
+        &mut content_result,
+    ));
+    preliminary_outputs.extend(authors::create_author_pages(
+        context,
+        &preliminary_outputs,
+        &mut collections,
+    ));
+    preliminary_outputs.extend(archive::create_archive_pages(context, &preliminary_outputs));
+    check_duplicate_links(&mut preliminary_outputs, &mut content_result);
+    // Set before related posts, which are rendered with templates that can use collections.
+    context
+        .collections
+        .set(collections)
+        .expect("Unable to set collections.");
+    let mut navigation =
+        series::create_navigation(context, &preliminary_outputs, &mut content_result);
+    related::create_related_posts(
+        context,
+        &preliminary_outputs,
+        &mut navigation,
+        &mut content_result,
+    );
     context
-        .md_post_list
-        .set(md_post_list)
-        .expect("Unable to set md_post_list.");
+        .navigation
+        .set(navigation)
+        .expect("Unable to set navigation.");
 
+    if context.mode == Mode::Build {
+        search::create_search_index(context, &preliminary_outputs).await?;
+    }
+
+    let mut links = Vec::new();
+    let mut pages = Vec::new();
     let mut set = JoinSet::new();
     for previous_step in &preliminary_outputs {
         let context = context.clone();
         let previous_step = previous_step.clone();
-        set.spawn(async move { process_file(context, previous_step).await });
+        set.spawn(async move {
+            process_file(context, previous_step.clone())
+                .await
+                .map_err(|error| Diagnostic::from(error).or_file(&previous_step.path))
+        });
     }
 
     while let Some(result) = set.join_next().await {
@@ -111,27 +218,63 @@ pub async fn process_content(context: &Arc<Context>) -> anyhow::Result<ContentRe
         };
 
         match result {
-            Ok(result) => {
-                content_result.errors.extend(result.errors);
-                content_result.warnings.extend(result.warnings);
+            Ok((result, file_links, file_pages)) => {
+                content_result.extend(result);
+                links.extend(file_links);
+                pages.extend(file_pages);
             }
-            Err(error) => {
-                content_result.push_error(error);
+            Err(diagnostic) => {
+                content_result.push(diagnostic);
                 continue;
             }
         }
     }
 
+    // Created from emitted pages, which include every page of paginated lists.
+    let routes =
+        redirects::create_routes(context, &preliminary_outputs, &links, &mut content_result)
+            .await?;
+
+    if context.mode == Mode::Check {
+        content_result.extend(links::check(context, &pages, &routes));
+    }
+
     Ok(content_result)
 }
 
+/// Reports pages claiming the same link and keeps only the first of them.
+fn check_duplicate_links(
+    outputs: &mut Vec<Arc<PreliminaryAnalysisOutput>>,
+    result: &mut ContentResult,
+) {
+    outputs.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut links = HashMap::<String, PathBuf>::new();
+    outputs.retain(|output| match links.get(&output.link) {
+        Some(existing) => {
+            result.push(
+                Diagnostic::error(
+                    diagnostic::DUPLICATE_PERMALINK,
+                    format!(
+                        "Page '/{}' is already generated from '{}'.",
+                        output.link,
+                        existing.display()
+                    ),
+                )
+                .with_file(&output.path),
+            );
+            false
+        }
+        None => {
+            links.insert(output.link.clone(), output.path.clone());
+            true
+        }
+    });
+}
+
 pub fn get_id_from_name(name: &str) -> String {
     let mut name = name;
-    if name
-        .as_bytes()
-        .first()
-        .map_or(false, |v| v.is_ascii_digit())
-    {
+    if name.as_bytes().first().is_some_and(|v| v.is_ascii_digit()) {
         name = &name[1..];
     }
 
@@ -151,6 +294,62 @@ pub fn get_id_from_name(name: &str) -> String {
     result
 }
 
+/// Escapes text for use in HTML content and quoted attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Creates a lowercase URL segment from the name. Latin letters with diacritics are transliterated,
+/// symbols which distinguish names like `C++` and `C#` are spelled out and other characters
+/// separate words. Letters of other scripts are kept.
+pub fn slugify(name: &str) -> String {
+    let mut result = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        match c {
+            c if c.is_ascii_alphanumeric() => result.push(c),
+            'ą' | 'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => result.push('a'),
+            'ć' | 'č' | 'ç' => result.push('c'),
+            'ď' => result.push('d'),
+            'ę' | 'è' | 'é' | 'ê' | 'ë' | 'ě' => result.push('e'),
+            'ì' | 'í' | 'î' | 'ï' => result.push('i'),
+            'ł' | 'ľ' => result.push('l'),
+            'ń' | 'ñ' | 'ň' => result.push('n'),
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' | 'ø' => result.push('o'),
+            'ř' => result.push('r'),
+            'ś' | 'š' => result.push('s'),
+            'ß' => result.push_str("ss"),
+            'ť' => result.push('t'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ů' => result.push('u'),
+            'ý' | 'ÿ' => result.push('y'),
+            'ź' | 'ż' | 'ž' => result.push('z'),
+            '+' => result.push_str("-plus-"),
+            '#' => result.push_str("-sharp-"),
+            '&' => result.push_str("-and-"),
+            '@' => result.push_str("-at-"),
+            c if c.is_alphanumeric() => result.push(c),
+            _ => result.push('-'),
+        }
+    }
+
+    result
+        .split('-')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
 fn collect_files_for_processing(path: &Path) -> Vec<PathBuf> {
     let mut files = Vec::new();
 
@@ -164,7 +363,14 @@ fn collect_files_for_processing(path: &Path) -> Vec<PathBuf> {
             continue;
         }
 
-        if file.file_name() == "_template.html" {
+        if [
+            "_template.html",
+            collections::TAG_TEMPLATE,
+            authors::AUTHOR_TEMPLATE,
+            archive::ARCHIVE_TEMPLATE,
+        ]
+        .contains(&file.file_name().to_str().unwrap_or_default())
+        {
             continue;
         }
 
@@ -181,36 +387,63 @@ fn collect_files_for_processing(path: &Path) -> Vec<PathBuf> {
 async fn process_file(
     context: Arc<Context>,
     previous_step: Arc<PreliminaryAnalysisOutput>,
-) -> anyhow::Result<ContentResult> {
+) -> anyhow::Result<(ContentResult, Vec<String>, Vec<links::Page>)> {
     tracing::trace!("Processing file '{}'.", previous_step.path.display());
 
+    let mut sources = vec![previous_step.path.as_path(), &previous_step.template_path];
+    sources.extend(context.templates.paths());
+
     let mut result = ContentResult::new();
-    let mut variables = previous_step.variables.clone();
-    let html = create_html_file(
-        &context,
-        &previous_step.template_path,
-        &mut variables,
-        &mut result,
-    )
-    .await?;
-
-    let mut output_path = Path::new(&context.args.output).join(
-        previous_step
-            .path
-            .strip_prefix(&context.args.project)
-            .expect("Unable to strip prefix."),
-    );
+    let mut links = Vec::new();
+    let mut pages = Vec::new();
+    let mut page = 0;
+    loop {
+        let mut variables = previous_step.variables.clone();
+        variables.page = page;
+        let mut html = create_html_file(
+            &context,
+            &previous_step.template_path,
+            &mut variables,
+            &mut result,
+        )
+        .await?;
+        variables.apply_pagination(&mut html, &context, &previous_step.link);
+
+        let link = collections::page_link(&previous_step.link, page);
+        match context.mode {
+            Mode::Check => pages.push(links::Page::scan(
+                link.clone(),
+                sources.iter().map(|p| p.to_path_buf()).collect(),
+                &html,
+            )),
+            Mode::Build => write_page(&context, &link, &html, &previous_step.path).await?,
+        }
+        links.push(link);
+
+        page += 1;
+        if page >= variables.page_count(&context) {
+            break;
+        }
+    }
+
+    result.locate(&sources);
+    Ok((result, links, pages))
+}
+
+async fn write_page(
+    context: &Context,
+    link: &str,
+    html: &str,
+    source: &Path,
+) -> anyhow::Result<()> {
+    let mut output_path = context.output.join("content").join(match link.is_empty() {
+        true => "index",
+        false => link,
+    });
     output_path.set_extension("html");
 
-    fs::create_dir_all(output_path.parent().unwrap())
-        .await
-        .expect("Unable to create directory.");
-    fs::File::create(&output_path)
-        .await
-        .expect("Unable to create file.")
-        .write_all(html.as_bytes())
-        .await
-        .expect("Unable to write file.");
+    let source = Some(source);
+    output::write(context, &output_path, html.as_bytes(), source).await?;
 
     let mut compressed = Vec::new();
     let mut encoder = ZlibEncoder::new(&mut compressed, Compression::best());
@@ -219,14 +452,8 @@ async fn process_file(
         .expect("Unable to write to encoder.");
 
     output_path.set_extension("html.deflate");
-    fs::File::create(&output_path)
-        .await
-        .expect("Unable to create file.")
-        .write_all(encoder.finish().expect("Unable to finish encoder."))
-        .await
-        .expect("Unable to write file.");
-
-    Ok(result)
+    let compressed = encoder.finish().expect("Unable to finish encoder.");
+    output::write(context, &output_path, compressed, source).await
 }
 
 async fn create_html_file(
@@ -238,8 +465,12 @@ async fn create_html_file(
     let mut file = match tokio::fs::File::open(&template_path).await {
         Ok(file) => file,
         Err(error) => {
-            tracing::error!("Unable to open file: {}.", error);
-            return Err(error.into());
+            return Err(Diagnostic::error(
+                diagnostic::IO,
+                format!("Unable to open file: {}.", error),
+            )
+            .with_file(template_path)
+            .into());
         }
     };
     let mut buffer = String::new();
@@ -282,13 +513,11 @@ async fn create_html_file(
             }
             Ok(Event::Eof) => break,
             Err(error) => {
-                tracing::error!(
-                    "Error in processing file `{}` at position {}: {:?}",
-                    template_path.display(),
-                    reader.buffer_position(),
-                    error,
+                return Err(
+                    Diagnostic::error(diagnostic::TEMPLATE_SYNTAX, error.to_string())
+                        .with_file(template_path)
+                        .into(),
                 );
-                return Err(error.into());
             }
             _ => (),
         }
@@ -296,12 +525,11 @@ async fn create_html_file(
         buf.clear();
     }
 
-    #[cfg(not(debug_assertions))]
-    let minified = minify::html::minify(reader.get_ref().get_ref());
-    #[cfg(debug_assertions)]
-    let minified = reader.into_inner().into_inner();
-
-    Ok(minified)
+    let html = reader.into_inner().into_inner();
+    match context.config.build.minify {
+        true => Ok(minify::html::minify(&html)),
+        false => Ok(html),
+    }
 }
 
 fn set_reader_position(
@@ -328,15 +556,16 @@ fn get_element_name<'a>(
 ) -> anyhow::Result<&'a str> {
     match std::str::from_utf8(e.0) {
         Ok(e) => Ok(e),
-        Err(error) => {
-            tracing::error!(
-                "Error in processing file `{}` at position {}: {:?}",
-                template_path.display(),
+        Err(error) => Err(Diagnostic::error(
+            diagnostic::TEMPLATE_SYNTAX,
+            format!(
+                "Element name at position {} is not valid UTF-8: {}.",
                 reader.buffer_position(),
                 error
-            );
-            Err(error.into())
-        }
+            ),
+        )
+        .with_file(template_path)
+        .into()),
     }
 }
 
@@ -352,7 +581,7 @@ fn upgrade_header(
         && element_name
             .as_bytes()
             .get(1)
-            .map_or(false, |v| v.is_ascii_digit() && *v != b'1'))
+            .is_some_and(|v| v.is_ascii_digit() && *v != b'1'))
         || last_start_position.is_none()
         || position < *last_edited_position + 1
     {
@@ -384,3 +613,32 @@ fn upgrade_header(
 
     *last_edited_position = position + 9;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_spells_out_symbols() {
+        assert_eq!(slugify("C++"), "c-plus-plus");
+        assert_eq!(slugify("C#"), "c-sharp");
+        assert_eq!(slugify("Rock & Roll"), "rock-and-roll");
+    }
+
+    #[test]
+    fn slugify_transliterates_and_collapses_separators() {
+        assert_eq!(slugify("Ćwiczenia"), "cwiczenia");
+        assert_eq!(slugify("  Hello,   World!  "), "hello-world");
+        assert_eq!(slugify("2024"), "2024");
+        assert_eq!(slugify("日本語"), "日本語");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn escape_html_escapes_quotes() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use chrono::Datelike;
+use vsm_config::{SortBy, SortOrder, DEFAULT_COLLECTION};
+
+use crate::Context;
+
+use super::{
+    collections, content_variables::ContentVariables, escape_html,
+    preliminary_analysis::PreliminaryAnalysisOutput,
+};
+
+/// Template of archive pages, generated at `<directory of the template>`, `<...>/<year>` and
+/// `<...>/<year>/<month>`.
+pub const ARCHIVE_TEMPLATE: &str = "_archive.html";
+
+/// Posts grouped by year and month, posts of a month are newest first.
+type Archive<'a> = BTreeMap<i32, BTreeMap<u32, Vec<&'a Arc<PreliminaryAnalysisOutput>>>>;
+
+pub fn create_archive_pages(
+    context: &Context,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+) -> Vec<Arc<PreliminaryAnalysisOutput>> {
+    let templates = collections::find_page_templates(&context.config, ARCHIVE_TEMPLATE);
+    if templates.is_empty() {
+        return Vec::new();
+    }
+
+    let mut config = context
+        .config
+        .collections
+        .get(DEFAULT_COLLECTION)
+        .cloned()
+        .unwrap_or_default();
+    config.sort_by = SortBy::Date;
+    config.order = SortOrder::Desc;
+    config.limit = None;
+    let posts = collections::collect_pages(context, DEFAULT_COLLECTION, &config, outputs);
+
+    let mut archive = Archive::new();
+    for post in &posts {
+        let date = post.content.as_ref().unwrap().date;
+        archive
+            .entry(date.year())
+            .or_default()
+            .entry(date.month())
+            .or_default()
+            .push(post);
+    }
+
+    let mut pages = Vec::new();
+    for template in &templates {
+        let root = collections::template_link(&context.config, template, "");
+        pages.push(create_page(template, &root, "", "", &archive, &root));
+
+        for (year, months) in &archive {
+            let mut year_archive = Archive::new();
+            year_archive.insert(*year, months.clone());
+            let link = collections::template_link(&context.config, template, &year.to_string());
+            pages.push(create_page(
+                template,
+                &link,
+                &year.to_string(),
+                "",
+                &year_archive,
+                &root,
+            ));
+
+            for (month, posts) in months {
+                let mut month_archive = Archive::new();
+                month_archive
+                    .entry(*year)
+                    .or_default()
+                    .insert(*month, posts.clone());
+                let link = format!("{}/{:02}", link, month);
+                pages.push(create_page(
+                    template,
+                    &link,
+                    &year.to_string(),
+                    &format!("{:02}", month),
+                    &month_archive,
+                    &root,
+                ));
+            }
+        }
+    }
+
+    tracing::trace!("Created {} archive pages.", pages.len());
+    pages
+}
+
+fn create_page(
+    template: &Path,
+    link: &str,
+    year: &str,
+    month: &str,
+    archive: &Archive,
+    root: &str,
+) -> Arc<PreliminaryAnalysisOutput> {
+    let mut variables = ContentVariables::new();
+    variables.insert("link".to_owned(), link.to_owned());
+    variables.insert("archive".to_owned(), render(archive, root));
+    variables.insert("archive_year".to_owned(), year.to_owned());
+    variables.insert("archive_month".to_owned(), month.to_owned());
+    variables.insert(
+        "archive_count".to_owned(),
+        count(archive.values().flat_map(|months| months.values())).to_string(),
+    );
+
+    Arc::new(PreliminaryAnalysisOutput {
+        path: template.to_path_buf(),
+        link: link.to_owned(),
+        template_path: template.to_path_buf(),
+        variables,
+        content: None,
+    })
+}
+
+fn render(archive: &Archive, root: &str) -> String {
+    let root = match root.is_empty() {
+        true => String::new(),
+        false => format!("/{}", root),
+    };
+
+    let mut result = r#"<div class="archive">"#.to_owned();
+    for (year, months) in archive.iter().rev() {
+        result.push_str(&format!(
+            r#"<section class="archive-year"><h2><a href="{0}/{1}">{1}</a> <span class="archive-count">{2}</span></h2>"#,
+            root,
+            year,
+            count(months.values())
+        ));
+
+        for (month, posts) in months.iter().rev() {
+            let date = posts[0].content.as_ref().unwrap().date;
+            result.push_str(&format!(
+                r#"<section class="archive-month"><h3><a href="{}/{}/{:02}">{}</a> <span class="archive-count">{}</span></h3><ul>"#,
+                root,
+                year,
+                month,
+                date.format("%B"),
+                posts.len()
+            ));
+
+            for post in posts {
+                let content = post.content.as_ref().unwrap();
+                result.push_str(&format!(
+                    r#"<li><a href="/{}">{}</a> <time datetime="{}">{}</time></li>"#,
+                    escape_html(&post.link),
+                    escape_html(&content.title),
+                    content.date.to_rfc3339(),
+                    content.date.format("%e %B")
+                ));
+            }
+
+            result.push_str("</ul></section>");
+        }
+
+        result.push_str("</section>");
+    }
+
+    result.push_str("</div>");
+    result
+}
+
+fn count<'a>(posts: impl Iterator<Item = &'a Vec<&'a Arc<PreliminaryAnalysisOutput>>>) -> usize {
+    posts.map(|posts| posts.len()).sum()
+}
@@ -0,0 +1,214 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use vsm_config::DEFAULT_COLLECTION;
+
+use crate::{Config, Context};
+
+use super::{
+    collections::{self, Collection},
+    content_variables::ContentVariables,
+    escape_html,
+    preliminary_analysis::PreliminaryAnalysisOutput,
+};
+
+/// Project level file with authors keyed by ids used in `author` and `authors` front matter.
+pub const AUTHORS_FILE: &str = "authors.toml";
+/// Template of author pages, generated for every author at `<directory of the template>/<id>`.
+pub const AUTHOR_TEMPLATE: &str = "_author.html";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Author {
+    pub name: String,
+    #[serde(default)]
+    pub bio: String,
+    pub avatar: Option<String>,
+    /// Keyed by labels, e.g. `GitHub`
+    #[serde(default)]
+    pub links: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Authors {
+    pub authors: BTreeMap<String, Author>,
+    /// Author page templates, pages are linked from posts when there is at least one
+    templates: Vec<PathBuf>,
+    /// Directory of author pages from the first template
+    directory: Option<String>,
+}
+
+impl Authors {
+    pub fn load(config: &Config) -> anyhow::Result<Self> {
+        let path = config.project.join(AUTHORS_FILE);
+        let authors = match path.exists() {
+            true => toml::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("Unable to parse '{}'", path.display()))?,
+            false => BTreeMap::new(),
+        };
+
+        let templates = collections::find_page_templates(config, AUTHOR_TEMPLATE);
+        let directory = templates
+            .first()
+            .map(|template| collections::template_link(config, template, ""));
+
+        Ok(Self {
+            authors,
+            templates,
+            directory,
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Author> {
+        self.authors.get(id)
+    }
+
+    /// Link of the author page, if author pages are generated.
+    pub fn link(&self, id: &str) -> Option<String> {
+        self.directory
+            .as_ref()
+            .map(|directory| match directory.is_empty() {
+                true => id.to_owned(),
+                false => format!("{}/{}", directory, id),
+            })
+    }
+
+    /// Bylines of the authors, linked to their pages when available.
+    pub fn render(&self, ids: &[String]) -> String {
+        let mut result = String::new();
+        for (id, author) in ids.iter().filter_map(|id| Some((id, self.get(id)?))) {
+            result.push_str(r#"<span class="author">"#);
+            if let Some(avatar) = &author.avatar {
+                result.push_str(&format!(
+                    r#"<img class="author-avatar" src="{}" alt="" />"#,
+                    escape_html(avatar)
+                ));
+            }
+            match self.link(id) {
+                Some(link) => result.push_str(&format!(
+                    r#"<a href="/{}">{}</a>"#,
+                    link,
+                    escape_html(&author.name)
+                )),
+                None => result.push_str(&escape_html(&author.name)),
+            }
+            result.push_str("</span>");
+        }
+        result
+    }
+
+    /// Schema.org persons of the authors, falls back to the site author.
+    pub fn json_ld(&self, context: &Context, ids: &[String]) -> Value {
+        let persons = ids
+            .iter()
+            .filter_map(|id| Some((id, self.get(id)?)))
+            .map(|(id, author)| {
+                let mut person = json!({
+                    "@type": "Person",
+                    "name": author.name,
+                });
+                if let Some(link) = self.link(id) {
+                    person["url"] = Value::String(context.get_absolute_url(&link));
+                }
+                if !author.bio.is_empty() {
+                    person["description"] = Value::String(author.bio.clone());
+                }
+                if let Some(avatar) = &author.avatar {
+                    person["image"] = Value::String(context.get_absolute_url(avatar));
+                }
+                if !author.links.is_empty() {
+                    person["sameAs"] = author.links.values().cloned().collect();
+                }
+                person
+            })
+            .collect::<Vec<_>>();
+
+        match persons.len() {
+            0 => json!({
+                "@type": "Person",
+                "name": context.config.site.author,
+                "url": context.get_absolute_url(""),
+            }),
+            1 => persons.into_iter().next().unwrap(),
+            _ => Value::Array(persons),
+        }
+    }
+}
+
+/// Creates pages for every author from author templates and collections listing their posts.
+pub fn create_author_pages(
+    context: &Context,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+    collections: &mut HashMap<String, Collection>,
+) -> Vec<Arc<PreliminaryAnalysisOutput>> {
+    let authors = &context.authors;
+    if authors.templates.is_empty() {
+        return Vec::new();
+    }
+
+    let mut config = context
+        .config
+        .collections
+        .get(DEFAULT_COLLECTION)
+        .cloned()
+        .unwrap_or_default();
+    config.limit = None;
+    let posts = collections::collect_pages(context, DEFAULT_COLLECTION, &config, outputs);
+
+    let mut pages = Vec::new();
+    for (id, author) in &authors.authors {
+        let name = format!("authors/{}", id);
+        let author_posts = posts
+            .iter()
+            .filter(|post| post.content.as_ref().unwrap().authors.contains(id))
+            .cloned()
+            .collect();
+        collections.insert(name.clone(), Collection::new(&config, author_posts));
+
+        let mut links = String::new();
+        for (label, url) in &author.links {
+            links.push_str(&format!(
+                r#"<li><a href="{}" rel="me">{}</a></li>"#,
+                escape_html(url),
+                escape_html(label)
+            ));
+        }
+        if !links.is_empty() {
+            links = format!(r#"<ul class="author-links">{}</ul>"#, links);
+        }
+
+        for template in &authors.templates {
+            let link = collections::template_link(&context.config, template, id);
+
+            let mut variables = ContentVariables::new();
+            variables.insert("link".to_owned(), link.clone());
+            variables.insert("author.id".to_owned(), id.clone());
+            variables.insert("author.name".to_owned(), escape_html(&author.name));
+            variables.insert("author.bio".to_owned(), escape_html(&author.bio));
+            variables.insert(
+                "author.avatar".to_owned(),
+                escape_html(author.avatar.as_deref().unwrap_or_default()),
+            );
+            variables.insert("author.links".to_owned(), links.clone());
+            variables.page_collection = Some(name.clone());
+
+            pages.push(Arc::new(PreliminaryAnalysisOutput {
+                path: template.clone(),
+                link,
+                template_path: template.clone(),
+                variables,
+                content: None,
+            }));
+        }
+    }
+
+    tracing::trace!("Created {} author pages.", pages.len());
+    pages
+}
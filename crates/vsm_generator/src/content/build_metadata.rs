@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::{git, Config};
+
+use super::escape_html;
+
+/// Variables of CI services with the branch name, used when the checkout is detached.
+const BRANCH_VARIABLES: [&str; 3] = ["GITHUB_REF_NAME", "CI_COMMIT_REF_NAME", "BRANCH_NAME"];
+
+/// Variables describing the build, resolved in every page.
+#[derive(Debug, Default)]
+pub struct BuildMetadata {
+    /// Keyed by variable names, e.g. `build.timestamp`, `git.commit` or `env.<name>`
+    pub variables: HashMap<String, String>,
+}
+
+impl BuildMetadata {
+    pub fn load(config: &Config) -> Self {
+        let now = Utc::now();
+        let commit = git::run(&config.project, &["rev-parse", "HEAD"]).unwrap_or_default();
+        let branch = match git::run(&config.project, &["rev-parse", "--abbrev-ref", "HEAD"]) {
+            Some(branch) if branch != "HEAD" => branch,
+            Some(_) => BRANCH_VARIABLES
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+
+        let mut variables = HashMap::from([
+            ("build.timestamp".to_owned(), now.to_rfc3339()),
+            ("build.year".to_owned(), now.format("%Y").to_string()),
+            (
+                "build.version".to_owned(),
+                env!("CARGO_PKG_VERSION").to_owned(),
+            ),
+            (
+                "build.profile".to_owned(),
+                config.profile.clone().unwrap_or_default(),
+            ),
+            (
+                "git.commit_short".to_owned(),
+                commit.chars().take(7).collect(),
+            ),
+            ("git.commit".to_owned(), commit),
+            ("git.branch".to_owned(), escape_html(&branch)),
+        ]);
+
+        for name in &config.build.env {
+            let value = std::env::var(name).unwrap_or_else(|_| {
+                tracing::warn!(
+                    "Environment variable '{}' listed in 'build.env' is not set.",
+                    name
+                );
+                String::new()
+            });
+            variables.insert(format!("env.{}", name), escape_html(&value));
+        }
+
+        Self { variables }
+    }
+}
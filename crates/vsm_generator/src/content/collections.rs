@@ -0,0 +1,380 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use vsm_config::{CollectionConfig, SortBy, SortOrder, DEFAULT_COLLECTION};
+use walkdir::WalkDir;
+
+use crate::{
+    diagnostic::{self, Diagnostic},
+    routes, Config, Context,
+};
+
+use super::{
+    content_variables::ContentVariables, escape_html, markdown,
+    preliminary_analysis::PreliminaryAnalysisOutput, slugify, ContentResult,
+};
+
+/// Template of tag pages, generated for every tag at `<directory of the template>/<tag>`.
+pub const TAG_TEMPLATE: &str = "_tag.html";
+
+#[derive(Debug)]
+pub struct Collection {
+    /// Sorted pages, always with markdown content
+    pub pages: Vec<Arc<PreliminaryAnalysisOutput>>,
+    per_page: Option<usize>,
+    empty: Option<String>,
+    template: Option<String>,
+}
+
+impl Collection {
+    pub fn new(config: &CollectionConfig, pages: Vec<Arc<PreliminaryAnalysisOutput>>) -> Self {
+        Self {
+            pages,
+            per_page: config.per_page.filter(|per_page| *per_page > 0),
+            empty: config.empty.clone(),
+            template: config.template.clone(),
+        }
+    }
+
+    pub fn is_paginated(&self) -> bool {
+        self.per_page.is_some()
+    }
+
+    pub fn page_count(&self) -> usize {
+        match self.per_page {
+            Some(per_page) => self.pages.len().div_ceil(per_page).max(1),
+            None => 1,
+        }
+    }
+
+    /// Renders the list of pages on the given page, starting from 0.
+    pub fn render(
+        &self,
+        context: &Arc<Context>,
+        page: usize,
+        result: &mut ContentResult,
+    ) -> String {
+        let per_page = self.per_page.unwrap_or(self.pages.len());
+        let pages = self.pages.iter().skip(page * per_page).take(per_page);
+
+        let template = self
+            .template
+            .as_ref()
+            .and_then(|t| context.templates.get(t));
+        let mut list = match template {
+            Some(template) => pages
+                .map(|page| render_item(context, &template.data, page, result))
+                .collect(),
+            None => render(pages),
+        };
+
+        if list.is_empty() {
+            match &self.empty {
+                Some(empty) => list.push_str(empty),
+                None => list.push_str("<p>Unfortunately, page still don't have any posts :(</p>"),
+            }
+        }
+
+        list
+    }
+}
+
+/// Link of the page of a paginated list, starting from 0 which is the list itself.
+pub fn page_link(link: &str, page: usize) -> String {
+    match (link.is_empty(), page) {
+        (_, 0) => link.to_owned(),
+        (true, _) => format!("page/{}", page + 1),
+        (false, _) => format!("{}/page/{}", link, page + 1),
+    }
+}
+
+pub fn create_collections(
+    context: &Context,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+    result: &mut ContentResult,
+) -> HashMap<String, Collection> {
+    for output in outputs {
+        let Some(content) = &output.content else {
+            continue;
+        };
+
+        for name in &content.collections {
+            if !context.config.collections.contains_key(name) {
+                let mut diagnostic = Diagnostic::warning(
+                    diagnostic::FRONT_MATTER,
+                    format!("Collection '{}' is not defined in the configuration.", name),
+                )
+                .with_needle(name);
+                diagnostic.locate([output.path.as_path()]);
+                result.push(diagnostic.or_file(&output.path));
+            }
+        }
+    }
+
+    context
+        .config
+        .collections
+        .iter()
+        .map(|(name, config)| {
+            let pages = collect_pages(context, name, config, outputs);
+            (name.clone(), Collection::new(config, pages))
+        })
+        .collect()
+}
+
+pub fn collect_pages(
+    context: &Context,
+    name: &str,
+    config: &CollectionConfig,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+) -> Vec<Arc<PreliminaryAnalysisOutput>> {
+    let mut pages = outputs
+        .iter()
+        .filter(|output| {
+            let Some(content) = &output.content else {
+                return false;
+            };
+            if (content.draft && !config.include_drafts) || routes::is_error_page(&output.link) {
+                return false;
+            }
+
+            includes(
+                context,
+                name,
+                config,
+                &output.path,
+                &content.tags,
+                &content.collections,
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    pages.sort_by(|a, b| {
+        let (a, b) = match config.order {
+            SortOrder::Asc => (a, b),
+            SortOrder::Desc => (b, a),
+        };
+        let (a, b) = (a.content.as_ref().unwrap(), b.content.as_ref().unwrap());
+        match config.sort_by {
+            SortBy::Date => a.date.cmp(&b.date),
+            SortBy::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        }
+    });
+
+    if let Some(limit) = config.limit {
+        pages.truncate(limit);
+    }
+
+    pages
+}
+
+/// Checks whether the page belongs to the collection, ignoring its draft status.
+pub fn includes(
+    context: &Context,
+    name: &str,
+    config: &CollectionConfig,
+    path: &Path,
+    tags: &[String],
+    collections: &[String],
+) -> bool {
+    if collections.iter().any(|c| c == name) {
+        return true;
+    }
+    if config.directory.is_none() && config.tags.is_empty() {
+        return false;
+    }
+
+    config
+        .directory
+        .as_ref()
+        .is_none_or(|directory| path.starts_with(context.config.project_content().join(directory)))
+        && (config.tags.is_empty() || tags.iter().any(|t| config.tags.contains(t)))
+}
+
+/// Creates pages for every tag from tag templates and collections listing pages with the tag. Tags
+/// with the same id, like `Rust` and `rust`, share one page.
+pub fn create_tag_pages(
+    context: &Context,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+    collections: &mut HashMap<String, Collection>,
+    result: &mut ContentResult,
+) -> Vec<Arc<PreliminaryAnalysisOutput>> {
+    let templates = find_page_templates(&context.config, TAG_TEMPLATE);
+    if templates.is_empty() {
+        return Vec::new();
+    }
+
+    let config = context
+        .config
+        .collections
+        .get(DEFAULT_COLLECTION)
+        .cloned()
+        .unwrap_or_default();
+    let tags = outputs
+        .iter()
+        .filter_map(|output| output.content.as_ref())
+        .filter(|content| !content.draft || config.include_drafts)
+        .flat_map(|content| content.tags.iter().cloned())
+        .collect::<BTreeSet<_>>();
+
+    let mut ids = BTreeMap::<String, Vec<String>>::new();
+    for tag in tags {
+        let id = slugify(&tag);
+        if id.is_empty() {
+            result.push(Diagnostic::warning(
+                diagnostic::FRONT_MATTER,
+                format!(
+                    "Tag '{}' has no characters usable in a link, its page is not generated.",
+                    tag
+                ),
+            ));
+            continue;
+        }
+        ids.entry(id).or_default().push(tag);
+    }
+
+    let mut pages = Vec::new();
+    for (id, tags) in ids {
+        if tags.len() > 1 {
+            result.push(Diagnostic::warning(
+                diagnostic::FRONT_MATTER,
+                format!(
+                    "Tags '{}' share the page '{}', pages with any of them are listed there.",
+                    tags.join("', '"),
+                    id
+                ),
+            ));
+        }
+
+        let tag = tags[0].clone();
+        let name = format!("tags/{}", id);
+        let config = CollectionConfig {
+            tags,
+            limit: None,
+            ..config.clone()
+        };
+        let collection_pages = collect_pages(context, &name, &config, outputs);
+        collections.insert(name.clone(), Collection::new(&config, collection_pages));
+
+        for template in &templates {
+            let link = template_link(&context.config, template, &id);
+
+            let mut variables = ContentVariables::new();
+            variables.insert("link".to_owned(), link.clone());
+            variables.insert("tag".to_owned(), tag.clone());
+            variables.page_collection = Some(name.clone());
+
+            pages.push(Arc::new(PreliminaryAnalysisOutput {
+                path: template.clone(),
+                link,
+                template_path: template.clone(),
+                variables,
+                content: None,
+            }));
+        }
+    }
+
+    tracing::trace!("Created {} tag pages.", pages.len());
+    pages
+}
+
+/// Templates of generated pages with the given file name, e.g. [`TAG_TEMPLATE`].
+pub fn find_page_templates(config: &Config, name: &str) -> Vec<PathBuf> {
+    WalkDir::new(config.project_content())
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() == name)
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Link of a page generated from the template, relative to the directory of the template.
+pub fn template_link(config: &Config, template: &Path, link: &str) -> String {
+    let directory = template
+        .parent()
+        .and_then(|p| p.strip_prefix(config.project_content()).ok())
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+
+    match (directory.is_empty(), link.is_empty()) {
+        (true, _) => link.to_owned(),
+        (false, true) => directory,
+        (false, false) => format!("{}/{}", directory, link),
+    }
+}
+
+/// Renders a page with markdown content through a template of a list item.
+/// Renders the template with variables of the page only, values can't contain variables that
+/// would be resolved later against the page listing the item.
+pub fn render_item(
+    context: &Arc<Context>,
+    template: &str,
+    page: &PreliminaryAnalysisOutput,
+    result: &mut ContentResult,
+) -> String {
+    let content = page.content.as_ref().unwrap();
+
+    let mut variables = ContentVariables::new();
+    for (key, value) in [
+        ("link", escape_html(&format!("/{}", page.link))),
+        ("title", escape_html(&content.title)),
+        ("description", escape_html(&content.description)),
+        ("excerpt", content.excerpt.clone()),
+        ("date", content.date.format("%e %B %Y").to_string()),
+        (
+            "image",
+            escape_html(content.image.as_deref().unwrap_or_default()),
+        ),
+        ("tags", markdown::render_tags(&content.tags)),
+    ] {
+        variables.insert(key.to_owned(), value.replace('{', "&#123;"));
+    }
+
+    let mut item = template.to_owned();
+    let len = item.len();
+    variables.apply(&mut item, 0..len, context, result);
+    item
+}
+
+/// Escapes a value of a list item, including braces, so it is not resolved as a variable of the
+/// page listing the item.
+fn escape_item(value: &str) -> String {
+    escape_html(value).replace('{', "&#123;")
+}
+
+fn render<'a>(pages: impl Iterator<Item = &'a Arc<PreliminaryAnalysisOutput>>) -> String {
+    let mut result = String::new();
+
+    for content in pages.filter_map(|page| page.content.as_ref()) {
+        result.push_str(
+            format!(
+                r#"<div class="post-list">
+                    <div class="post-list-top">
+                        <a href="/{}">{}</a>
+                        <div class="tooltip-wrapper">
+                            {}
+                            <div class="tooltip">{}</div>
+                        </div>
+                    </div>
+                    <p>{}</p>
+                    <div class="post-list-tags">"#,
+                escape_item(&content.link),
+                escape_item(&content.title),
+                content.date.format("%e&nbsp;%B&nbsp;%Y"),
+                content.date.format("%A, %e %B %Y %H:%M:%S UTC"),
+                escape_item(&content.description)
+            )
+            .as_str(),
+        );
+
+        result.push_str(&markdown::render_tags(&content.tags));
+
+        result.push_str("</div></div>");
+    }
+
+    result
+}
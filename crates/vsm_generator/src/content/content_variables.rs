@@ -1,19 +1,48 @@
 use std::{collections::HashMap, ops::Range, sync::Arc};
 
-use crate::Context;
+use serde_json::Value;
+use vsm_config::DEFAULT_COLLECTION;
 
-use super::ContentResult;
+use crate::{
+    diagnostic::{self, Diagnostic},
+    Context,
+};
+
+use super::{collections, data, escape_html, ContentResult};
+
+const PAGINATION_VARIABLES: [&str; 5] = [
+    "pagination",
+    "pagination.prev",
+    "pagination.next",
+    "pagination.page",
+    "pagination.pages",
+];
+
+const NAVIGATION_VARIABLES: [&str; 4] =
+    ["series_nav", "prev_post", "next_post", "md_related_posts"];
 
 #[derive(Debug, Default, Clone)]
 pub struct ContentVariables {
     pub variables: HashMap<String, String>,
+    /// Index of the rendered page of paginated collections, starting from 0
+    pub page: usize,
+    /// First paginated collection rendered into the file, set while applying variables
+    pub paginated: Option<String>,
+    /// Collection of posts of a generated tag or author page, rendered by `{{tag_post_list}}` and
+    /// `{{author_post_list}}`
+    pub page_collection: Option<String>,
+    /// Elements bound by enclosing `{{for <name> in <array>}}` loops, set while applying variables
+    bindings: HashMap<String, Value>,
 }
 
 impl ContentVariables {
     pub fn new() -> Self {
         let mut variables = HashMap::new();
         variables.insert("warning".to_owned(), String::new());
-        Self { variables }
+        Self {
+            variables,
+            ..Default::default()
+        }
     }
 
     pub fn insert(&mut self, key: String, value: String) {
@@ -32,10 +61,7 @@ impl ContentVariables {
             let mut end = match data[range.start..range.end].find("}}") {
                 Some(end) => range.start + end + 2,
                 None => {
-                    result.push_error(anyhow::anyhow!(
-                        "Unable to find end of variable. In position {}.",
-                        range.start
-                    ));
+                    result.push(unterminated_variable(&data[range.start..range.end]));
                     return;
                 }
             };
@@ -48,10 +74,7 @@ impl ContentVariables {
                     end = match data[end..range.end].find("}}") {
                         Some(new_end) => end + new_end + 2,
                         None => {
-                            result.push_error(anyhow::anyhow!(
-                                "Unable to find end of variable. In position {}.",
-                                range.start
-                            ));
+                            result.push(unterminated_variable(&data[range.start..range.end]));
                             return;
                         }
                     };
@@ -71,24 +94,382 @@ impl ContentVariables {
                 continue;
             }
 
-            let variable_content = match key {
-                "md_post_list" => context.md_post_list.get().unwrap(),
-                _ => match self.variables.get(key) {
-                    Some(variable_content) => variable_content,
+            if key == "endfor" {
+                result.push(
+                    Diagnostic::error(
+                        diagnostic::TEMPLATE_SYNTAX,
+                        "Found '{{endfor}}' without a matching '{{for}}'.",
+                    )
+                    .with_needle(&data[range.start..end]),
+                );
+                return;
+            }
+
+            if let Some(header) = key.strip_prefix("for ") {
+                let Some((name, source)) = header.split_once(" in ") else {
+                    result.push(
+                        Diagnostic::error(
+                            diagnostic::TEMPLATE_SYNTAX,
+                            "Expected loop in format '{{for <name> in <array>}}'.",
+                        )
+                        .with_needle(&data[range.start..end]),
+                    );
+                    return;
+                };
+                let (name, source) = (name.trim(), source.trim());
+
+                let Some((body_end, loop_end)) = find_loop_end(data, end..range.end) else {
+                    result.push(
+                        Diagnostic::error(
+                            diagnostic::TEMPLATE_SYNTAX,
+                            "Unable to find '{{endfor}}' of the loop.",
+                        )
+                        .with_needle(&data[range.start..end]),
+                    );
+                    return;
+                };
+
+                let items = match self.lookup(context, source) {
+                    Some(Value::Array(items)) => items.clone(),
+                    Some(_) => {
+                        result.push(
+                            Diagnostic::error(
+                                diagnostic::TEMPLATE_SYNTAX,
+                                format!("Variable '{}' is not an array.", source),
+                            )
+                            .with_needle(&data[range.start..end]),
+                        );
+                        return;
+                    }
                     None => {
-                        result.push_error(anyhow::anyhow!(
-                            "Unable to find variable with key '{}'",
-                            key
-                        ));
+                        result.push(
+                            Diagnostic::error(
+                                diagnostic::UNDEFINED_VARIABLE,
+                                format!("Unable to find variable with key '{}'.", source),
+                            )
+                            .with_needle(&data[range.start..end]),
+                        );
+                        return;
+                    }
+                };
+
+                let body = data[end..body_end].to_owned();
+                let shadowed = self.bindings.remove(name);
+                // Variables set inside the body are scoped to a single iteration.
+                let variables = self.variables.clone();
+                let mut output = String::new();
+                for item in items {
+                    self.bindings.insert(name.to_owned(), item);
+                    let mut item_body = body.clone();
+                    let len = item_body.len();
+                    self.apply(&mut item_body, 0..len, context, result);
+                    output.push_str(&item_body);
+                    self.variables.clone_from(&variables);
+                }
+                self.bindings.remove(name);
+                if let Some(shadowed) = shadowed {
+                    self.bindings.insert(name.to_owned(), shadowed);
+                }
+
+                data.replace_range(range.start..loop_end, &output);
+
+                range.end = range.end + output.len() - (loop_end - range.start);
+                range.start += output.len();
+                continue;
+            }
+
+            // Values of data files are escaped unless requested as `{{raw data.<...>}}`.
+            let (raw, key) = match key.strip_prefix("raw ") {
+                Some(key) => (true, key.trim()),
+                None => (false, key),
+            };
+
+            let collection = match key {
+                "md_post_list" => Some(DEFAULT_COLLECTION),
+                "tag_post_list" | "author_post_list" => self.page_collection.as_deref(),
+                _ => key.strip_prefix("collection."),
+            };
+            let variable_content = match collection {
+                Some(name) => match context.collections.get().unwrap().get(name) {
+                    Some(collection) => {
+                        if collection.is_paginated() && self.paginated.is_none() {
+                            self.paginated = Some(name.to_owned());
+                        }
+                        collection.render(context, self.page, result)
+                    }
+                    None => {
+                        result.push(
+                            Diagnostic::error(
+                                diagnostic::UNDEFINED_VARIABLE,
+                                format!("Collection '{}' is not defined.", name),
+                            )
+                            .with_needle(&data[range.start..end]),
+                        );
+                        return;
+                    }
+                },
+                None if NAVIGATION_VARIABLES.contains(&key) => {
+                    let navigation = self
+                        .variables
+                        .get("link")
+                        .and_then(|link| context.navigation.get()?.get(link));
+                    match (navigation, key) {
+                        (Some(navigation), "series_nav") => navigation.series_nav.clone(),
+                        (Some(navigation), "prev_post") => navigation.prev_post.clone(),
+                        (Some(navigation), "next_post") => navigation.next_post.clone(),
+                        (Some(navigation), "md_related_posts") => navigation.related_posts.clone(),
+                        _ => String::new(),
+                    }
+                }
+                // Resolved by `apply_pagination` once the paginated collection is known.
+                None if PAGINATION_VARIABLES.contains(&key) => pagination_placeholder(key),
+                None => match (self.lookup(context, key), self.variables.get(key)) {
+                    (Some(value), _) => match data::render(value) {
+                        Some(variable_content) if raw => variable_content,
+                        Some(variable_content) => escape_html(&variable_content),
+                        None => {
+                            result.push(
+                                Diagnostic::error(
+                                    diagnostic::TEMPLATE_SYNTAX,
+                                    format!(
+                                        "Variable '{}' is a table, use its fields or a loop.",
+                                        key
+                                    ),
+                                )
+                                .with_needle(&data[range.start..end]),
+                            );
+                            return;
+                        }
+                    },
+                    (None, Some(variable_content)) => variable_content.clone(),
+                    (None, None) if context.metadata.variables.contains_key(key) => {
+                        context.metadata.variables[key].clone()
+                    }
+                    (None, None) if key.starts_with("env.") => {
+                        result.push(
+                            Diagnostic::error(
+                                diagnostic::UNDEFINED_VARIABLE,
+                                format!(
+                                    "Environment variable '{}' is not listed in 'build.env'.",
+                                    &key[4..]
+                                ),
+                            )
+                            .with_needle(&data[range.start..end]),
+                        );
+                        return;
+                    }
+                    (None, None) => {
+                        result.push(
+                            Diagnostic::error(
+                                diagnostic::UNDEFINED_VARIABLE,
+                                format!("Unable to find variable with key '{}'.", key),
+                            )
+                            .with_needle(&data[range.start..end]),
+                        );
                         return;
                     }
                 },
             };
 
-            data.replace_range(range.start..end, variable_content);
+            data.replace_range(range.start..end, &variable_content);
 
             range.end = range.end + variable_content.len() - (end - range.start);
             range.start += variable_content.len();
         }
     }
+
+    /// Finds values of loop variables and data files, e.g. `project.name` or `data.projects`.
+    fn lookup<'a>(&'a self, context: &'a Context, key: &str) -> Option<&'a Value> {
+        let (root, path) = match key.split_once('.') {
+            Some((root, path)) => (root, Some(path)),
+            None => (key, None),
+        };
+        let value = match self.bindings.get(root) {
+            Some(value) => value,
+            None if root == "data" => &context.data,
+            None => return None,
+        };
+
+        match path {
+            Some(path) => data::lookup(value, path),
+            None => Some(value),
+        }
+    }
+
+    /// Number of pages of the paginated collection rendered into the file.
+    pub fn page_count(&self, context: &Context) -> usize {
+        self.paginated
+            .as_ref()
+            .and_then(|name| context.collections.get().unwrap().get(name))
+            .map_or(1, |collection| collection.page_count())
+    }
+
+    /// Replaces pagination variables with navigation of the paginated collection, `link` is the
+    /// link of the first page.
+    pub fn apply_pagination(&self, html: &mut String, context: &Context, link: &str) {
+        replace_pagination(html, link, self.page, self.page_count(context));
+    }
+}
+
+/// Replaces pagination placeholders of the `current` page out of `pages`, starting from 0.
+fn replace_pagination(html: &mut String, link: &str, current: usize, pages: usize) {
+    let page_link = |page: usize| match page {
+        0 => format!("/{}", link),
+        _ => format!("/{}", collections::page_link(link, page)),
+    };
+    let prev = (current > 0).then(|| page_link(current - 1));
+    let next = (current + 1 < pages).then(|| page_link(current + 1));
+
+    let mut navigation = String::new();
+    if pages > 1 {
+        navigation.push_str(r#"<nav class="pagination">"#);
+        if let Some(prev) = &prev {
+            navigation.push_str(&format!(
+                r#"<a class="pagination-prev" href="{}">Previous</a>"#,
+                prev
+            ));
+        }
+        for page in 0..pages {
+            match page == current {
+                true => navigation.push_str(&format!(
+                    r#"<span class="pagination-current">{}</span>"#,
+                    page + 1
+                )),
+                false => navigation.push_str(&format!(
+                    r#"<a href="{}">{}</a>"#,
+                    page_link(page),
+                    page + 1
+                )),
+            }
+        }
+        if let Some(next) = &next {
+            navigation.push_str(&format!(
+                r#"<a class="pagination-next" href="{}">Next</a>"#,
+                next
+            ));
+        }
+        navigation.push_str("</nav>");
+    }
+
+    for (key, value) in [
+        ("pagination", navigation),
+        ("pagination.prev", prev.unwrap_or_default()),
+        ("pagination.next", next.unwrap_or_default()),
+        ("pagination.page", (current + 1).to_string()),
+        ("pagination.pages", pages.to_string()),
+    ] {
+        *html = html.replace(&pagination_placeholder(key), &value);
+    }
+}
+
+/// Returns the start and the end of `{{endfor}}` matching a loop whose body starts at the range.
+fn find_loop_end(data: &str, range: Range<usize>) -> Option<(usize, usize)> {
+    const END: &str = "{{endfor}}";
+
+    let mut depth = 0;
+    let mut position = range.start;
+    while position < range.end {
+        let start = position + data[position..range.end].find("{{")?;
+        if data[start..range.end].starts_with("{{for ") {
+            depth += 1;
+        } else if data[start..range.end].starts_with(END) {
+            if depth == 0 {
+                return Some((start, start + END.len()));
+            }
+            depth -= 1;
+        }
+        position = start + 2;
+    }
+
+    None
+}
+
+fn pagination_placeholder(key: &str) -> String {
+    format!("@@vsm:{}@@", key)
+}
+
+fn unterminated_variable(data: &str) -> Diagnostic {
+    let line = data.lines().next().unwrap_or_default();
+    Diagnostic::error(
+        diagnostic::UNTERMINATED_VARIABLE,
+        "Unable to find end of variable.",
+    )
+    .with_needle(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders() -> String {
+        PAGINATION_VARIABLES
+            .iter()
+            .map(|key| pagination_placeholder(key))
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+
+    #[test]
+    fn replaces_placeholders_of_middle_page() {
+        let mut html = placeholders();
+        replace_pagination(&mut html, "blog", 1, 3);
+        assert_eq!(
+            html,
+            concat!(
+                r#"<nav class="pagination"><a class="pagination-prev" href="/blog">Previous</a>"#,
+                r#"<a href="/blog">1</a><span class="pagination-current">2</span>"#,
+                r#"<a href="/blog/page/3">3</a>"#,
+                r#"<a class="pagination-next" href="/blog/page/3">Next</a></nav>"#,
+                "|/blog|/blog/page/3|2|3"
+            )
+        );
+    }
+
+    #[test]
+    fn omits_links_outside_of_pages() {
+        let mut html = placeholders();
+        replace_pagination(&mut html, "", 0, 2);
+        assert!(html.ends_with("||/page/2|1|2"));
+        assert!(!html.contains("pagination-prev"));
+
+        let mut html = placeholders();
+        replace_pagination(&mut html, "", 1, 2);
+        assert!(html.ends_with("|/||2|2"));
+        assert!(!html.contains("pagination-next"));
+    }
+
+    #[test]
+    fn finds_matching_loop_end() {
+        let data = "{{for a in x}}A{{for b in a.y}}B{{endfor}}C{{endfor}}D";
+        let body = "{{for a in x}}".len();
+        let (start, end) = find_loop_end(data, body..data.len()).unwrap();
+        assert_eq!(&data[body..start], "A{{for b in a.y}}B{{endfor}}C");
+        assert_eq!(&data[end..], "D");
+    }
+
+    #[test]
+    fn skips_other_variables_in_loop() {
+        let data = "{{for a in x}}{{a.name}}{{b:1}}{{endfor}}";
+        let body = "{{for a in x}}".len();
+        let (start, end) = find_loop_end(data, body..data.len()).unwrap();
+        assert_eq!(&data[body..start], "{{a.name}}{{b:1}}");
+        assert_eq!(end, data.len());
+    }
+
+    #[test]
+    fn requires_loop_end_in_range() {
+        let data = "{{for a in x}}{{for b in y}}{{endfor}}";
+        let body = "{{for a in x}}".len();
+        assert_eq!(find_loop_end(data, body..data.len()), None);
+
+        let data = "{{for a in x}}A{{endfor}}";
+        assert_eq!(find_loop_end(data, body..body + 3), None);
+    }
+
+    #[test]
+    fn omits_navigation_of_single_page() {
+        let mut html = placeholders();
+        replace_pagination(&mut html, "blog", 0, 1);
+        assert_eq!(html, "|||1|1");
+    }
 }
@@ -0,0 +1,155 @@
+use std::fs;
+
+use anyhow::Context as _;
+use serde_json::{Map, Value};
+
+use crate::Config;
+
+/// Project level directory with data files, exposed to templates as `{{data.<file name>}}`, or
+/// `{{raw data.<file name>}}` to insert values without escaping them.
+pub const DATA_DIRECTORY: &str = "data";
+
+/// Loads `json`, `toml` and `csv` files from the data directory into an object keyed by file names
+/// without extensions. Rows of `csv` files are objects keyed by the header.
+pub fn load(config: &Config) -> anyhow::Result<Value> {
+    let directory = config.project.join(DATA_DIRECTORY);
+    let mut data = Map::new();
+    if !directory.exists() {
+        return Ok(Value::Object(data));
+    }
+
+    let mut entries = fs::read_dir(&directory)
+        .with_context(|| format!("Unable to read '{}'", directory.display()))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+
+    for entry in entries {
+        let path = entry.path();
+        let (Some(name), Some(extension)) = (
+            path.file_stem().and_then(|name| name.to_str()),
+            path.extension().and_then(|extension| extension.to_str()),
+        ) else {
+            continue;
+        };
+
+        let parse = || -> anyhow::Result<Option<Value>> {
+            let text = fs::read_to_string(&path)?;
+            Ok(Some(match extension {
+                "json" => serde_json::from_str(&text)?,
+                "toml" => serde_json::to_value(toml::from_str::<toml::Value>(&text)?)?,
+                "csv" => parse_csv(&text)?,
+                _ => return Ok(None),
+            }))
+        };
+        let Some(value) =
+            parse().with_context(|| format!("Unable to parse '{}'", path.display()))?
+        else {
+            tracing::warn!(
+                "Ignoring data file with unsupported format: '{}'.",
+                path.display()
+            );
+            continue;
+        };
+
+        if data.insert(name.to_owned(), value).is_some() {
+            anyhow::bail!("Data file '{}' is defined more than once", name);
+        }
+        tracing::trace!("Loaded data file: '{}'.", path.display());
+    }
+
+    Ok(Value::Object(data))
+}
+
+/// Finds a value by its dot separated path, array elements are indexed by numbers.
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Object(object) => object.get(key),
+        Value::Array(array) => array.get(key.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Renders scalars and arrays of scalars, tables have to be accessed by their fields.
+pub fn render(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::Bool(bool) => Some(bool.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(str) => Some(str.clone()),
+        Value::Array(array) => Some(
+            array
+                .iter()
+                .map(|value| match value {
+                    Value::Array(_) | Value::Object(_) => None,
+                    value => render(value),
+                })
+                .collect::<Option<Vec<_>>>()?
+                .join(", "),
+        ),
+        Value::Object(_) => None,
+    }
+}
+
+fn parse_csv(text: &str) -> anyhow::Result<Value> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(Value::Object(
+            headers
+                .iter()
+                .zip(record.iter())
+                .map(|(header, field)| (header.to_owned(), Value::String(field.to_owned())))
+                .collect(),
+        ));
+    }
+
+    Ok(Value::Array(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn looks_up_nested_values() {
+        let value = json!({"site": {"links": [{"name": "a"}, {"name": "b"}]}});
+        assert_eq!(
+            lookup(&value, "site.links.1.name"),
+            Some(&Value::String("b".to_owned()))
+        );
+        assert_eq!(lookup(&value, "site.links.2"), None);
+        assert_eq!(lookup(&value, "site.links.name"), None);
+        assert_eq!(lookup(&value, "site.missing"), None);
+        assert_eq!(lookup(&value, "site.links.0.name.more"), None);
+    }
+
+    #[test]
+    fn renders_scalars_and_arrays_of_scalars() {
+        assert_eq!(render(&json!("a")).as_deref(), Some("a"));
+        assert_eq!(render(&json!(1.5)).as_deref(), Some("1.5"));
+        assert_eq!(render(&json!(null)).as_deref(), Some(""));
+        assert_eq!(
+            render(&json!([1, "b", true])).as_deref(),
+            Some("1, b, true")
+        );
+        assert_eq!(render(&json!({"a": 1})), None);
+        assert_eq!(render(&json!([[1]])), None);
+    }
+
+    #[test]
+    fn parses_csv_rows_by_header() {
+        let value = parse_csv("name,url\nRust,https://rust-lang.org\n\"A, B\",x\n").unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {"name": "Rust", "url": "https://rust-lang.org"},
+                {"name": "A, B", "url": "x"}
+            ])
+        );
+    }
+}
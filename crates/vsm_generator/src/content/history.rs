@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{git, Config};
+
+use super::escape_html;
+
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub hash: String,
+    /// Author date of the commit
+    pub date: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Git history of content files, empty when the project is not a repository.
+#[derive(Debug, Default)]
+pub struct History {
+    /// Keyed by paths relative to the project, revisions are newest first
+    files: HashMap<PathBuf, Vec<Revision>>,
+}
+
+impl History {
+    pub fn load(config: &Config) -> Self {
+        let project = &config.project;
+        let content = config.project_content();
+        let content = content.strip_prefix(project).unwrap_or(&content);
+        let (Some(prefix), Some(log)) = (
+            git::run(project, &["rev-parse", "--show-prefix"]),
+            git::run(
+                project,
+                &[
+                    "-c",
+                    "core.quotepath=off",
+                    "log",
+                    "--format=%x1e%H%x1f%aI%x1f%s",
+                    "--name-status",
+                    "-M",
+                    "--",
+                    &content.to_string_lossy(),
+                ],
+            ),
+        ) else {
+            return Self::default();
+        };
+
+        if git::run(project, &["rev-parse", "--is-shallow-repository"]).as_deref() == Some("true") {
+            tracing::warn!(
+                "Repository is a shallow clone, history of pages is incomplete. Fetch full history \
+                 for correct update dates."
+            );
+        }
+
+        let files = parse_log(&log, &prefix);
+        tracing::trace!("Loaded git history of {} files.", files.len());
+        Self { files }
+    }
+
+    /// Revisions of the file, newest first.
+    pub fn get(&self, config: &Config, path: &Path) -> &[Revision] {
+        path.strip_prefix(&config.project)
+            .ok()
+            .and_then(|path| self.files.get(path))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Parses `git log --name-status` output with records starting with `\x1e` and header fields
+/// separated by `\x1f`. Revisions from before a rename are assigned to the current path, paths are
+/// made relative to the project by stripping the `prefix`.
+fn parse_log(log: &str, prefix: &str) -> HashMap<PathBuf, Vec<Revision>> {
+    let mut files = HashMap::<PathBuf, Vec<Revision>>::new();
+    // Older paths of renamed files, mapped to their current paths.
+    let mut renames = HashMap::<&str, &str>::new();
+    for record in log.split('\x1e').filter(|record| !record.is_empty()) {
+        let mut lines = record.lines();
+        let mut header = lines.next().unwrap_or_default().split('\x1f');
+        let (Some(hash), Some(Ok(date)), Some(message)) = (
+            header.next(),
+            header.next().map(DateTime::parse_from_rfc3339),
+            header.next(),
+        ) else {
+            tracing::warn!("Unable to parse git log record: '{}'.", record);
+            continue;
+        };
+
+        let revision = Revision {
+            hash: hash.to_owned(),
+            date: date.with_timezone(&Utc),
+            message: message.to_owned(),
+        };
+        for line in lines.filter(|line| !line.is_empty()) {
+            let mut fields = line.split('\t');
+            let status = fields.next().unwrap_or_default();
+            let (old, file) = match (fields.next(), fields.next()) {
+                (Some(old), Some(new)) if status.starts_with('R') => (Some(old), new),
+                (Some(_), Some(new)) => (None, new),
+                (Some(file), None) => (None, file),
+                (None, _) => continue,
+            };
+
+            let file = renames.get(file).copied().unwrap_or(file);
+            if let Some(old) = old {
+                renames.insert(old, file);
+            }
+            if let Some(file) = file.strip_prefix(prefix) {
+                files
+                    .entry(PathBuf::from(file))
+                    .or_default()
+                    .push(revision.clone());
+            }
+        }
+    }
+
+    files
+}
+
+pub fn render(revisions: &[Revision]) -> String {
+    if revisions.is_empty() {
+        return String::new();
+    }
+
+    let mut result = r#"<ol class="history">"#.to_owned();
+    for revision in revisions {
+        result.push_str(&format!(
+            r#"<li><time datetime="{}">{}</time> {} <code>{}</code></li>"#,
+            revision.date.to_rfc3339(),
+            revision.date.format("%e %B %Y"),
+            escape_html(&revision.message),
+            &revision.hash[..revision.hash.len().min(7)]
+        ));
+    }
+    result.push_str("</ol>");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(hash: &str, date: &str, message: &str, files: &[&str]) -> String {
+        format!(
+            "\x1e{}\x1f{}\x1f{}\n\n{}\n",
+            hash,
+            date,
+            message,
+            files.join("\n")
+        )
+    }
+
+    fn messages(files: &HashMap<PathBuf, Vec<Revision>>, path: &str) -> Vec<String> {
+        files[Path::new(path)]
+            .iter()
+            .map(|revision| revision.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn groups_revisions_by_file() {
+        let log = [
+            record(
+                "b",
+                "2024-02-01T12:00:00+01:00",
+                "Edit",
+                &["M\tsite/content/a.md"],
+            ),
+            record(
+                "a",
+                "2024-01-01T12:00:00Z",
+                "Add",
+                &["A\tsite/content/a.md", "A\tsite/content/b.md"],
+            ),
+        ]
+        .concat();
+
+        let files = parse_log(&log, "site/");
+        assert_eq!(files.len(), 2);
+        assert_eq!(messages(&files, "content/a.md"), ["Edit", "Add"]);
+        assert_eq!(messages(&files, "content/b.md"), ["Add"]);
+
+        let revision = &files[Path::new("content/a.md")][0];
+        assert_eq!(revision.hash, "b");
+        assert_eq!(revision.date.to_rfc3339(), "2024-02-01T11:00:00+00:00");
+    }
+
+    #[test]
+    fn follows_renames() {
+        let log = [
+            record("c", "2024-03-01T00:00:00Z", "Edit", &["M\tcontent/c.md"]),
+            record(
+                "b",
+                "2024-02-01T00:00:00Z",
+                "Rename again",
+                &["R100\tcontent/b.md\tcontent/c.md"],
+            ),
+            record(
+                "a",
+                "2024-01-15T00:00:00Z",
+                "Rename",
+                &["R090\tcontent/a.md\tcontent/b.md"],
+            ),
+            record("0", "2024-01-01T00:00:00Z", "Add", &["A\tcontent/a.md"]),
+        ]
+        .concat();
+
+        let files = parse_log(&log, "");
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            messages(&files, "content/c.md"),
+            ["Edit", "Rename again", "Rename", "Add"]
+        );
+    }
+
+    #[test]
+    fn skips_invalid_records_and_other_directories() {
+        let log = [
+            record("a", "not a date", "Broken", &["M\tsite/content/a.md"]),
+            record("b", "2024-01-01T00:00:00Z", "Other", &["M\tother/a.md"]),
+            record(
+                "c",
+                "2024-01-01T00:00:00Z",
+                "Valid",
+                &["M\tsite/content/a.md"],
+            ),
+        ]
+        .concat();
+
+        let files = parse_log(&log, "site/");
+        assert_eq!(files.len(), 1);
+        assert_eq!(messages(&files, "content/a.md"), ["Valid"]);
+    }
+}
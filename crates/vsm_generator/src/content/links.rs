@@ -0,0 +1,310 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use url::Url;
+
+use crate::{
+    diagnostic::{self, Diagnostic},
+    routes::Routes,
+    Context,
+};
+
+use super::ContentResult;
+
+/// Base used only to resolve relative links, it never leaves this module.
+const RESOLVE_BASE: &str = "http://vsm.invalid/";
+
+#[derive(Debug)]
+pub struct Page {
+    pub link: String,
+    pub sources: Vec<PathBuf>,
+    ids: HashSet<String>,
+    links: Vec<String>,
+}
+
+impl Page {
+    pub fn scan(link: String, sources: Vec<PathBuf>, html: &str) -> Self {
+        let mut ids = HashSet::new();
+        let mut links = Vec::new();
+        for_each_attribute(html, |name, value| {
+            if name.eq_ignore_ascii_case("id") || name.eq_ignore_ascii_case("name") {
+                ids.insert(value.to_owned());
+            } else if name.eq_ignore_ascii_case("href") || name.eq_ignore_ascii_case("src") {
+                links.push(value.to_owned());
+            }
+        });
+
+        Self {
+            link,
+            sources,
+            ids,
+            links,
+        }
+    }
+
+    /// Links generated by vsm itself are not present in any source, so these point at the page.
+    fn locate(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+        diagnostic.locate(self.sources.iter().map(|p| p.as_path()));
+        match self.sources.first() {
+            Some(source) => diagnostic.or_file(source),
+            None => diagnostic,
+        }
+    }
+}
+
+pub fn check(context: &Context, pages: &[Page], routes: &Routes) -> ContentResult {
+    check_pages(
+        &context.config.site.base_url,
+        &context.config.project,
+        pages,
+        routes,
+    )
+}
+
+fn check_pages(base_url: &str, project: &Path, pages: &[Page], routes: &Routes) -> ContentResult {
+    let by_link = pages
+        .iter()
+        .map(|page| (page.link.as_str(), page))
+        .collect::<HashMap<_, _>>();
+
+    let mut result = ContentResult::new();
+    for page in pages {
+        for link in &page.links {
+            let Some((path, fragment)) = resolve(base_url, &page.link, link) else {
+                continue;
+            };
+
+            let target = match by_link.get(path.as_str()) {
+                Some(target) => *target,
+                None if is_static_file(project, &path)
+                    || is_server_route(&path)
+                    || routes.redirect(&path).is_some() =>
+                {
+                    continue
+                }
+                None => {
+                    result.push(
+                        page.locate(
+                            Diagnostic::error(
+                                diagnostic::BROKEN_LINK,
+                                format!("Link '{}' on page '/{}' does not exist.", link, page.link),
+                            )
+                            .with_needle(link),
+                        ),
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(fragment) = fragment {
+                if !target.ids.contains(&fragment) {
+                    result.push(
+                        page.locate(
+                            Diagnostic::error(
+                                diagnostic::BROKEN_ANCHOR,
+                                format!(
+                                    "Anchor '#{}' of link '{}' on page '/{}' does not exist.",
+                                    fragment, link, page.link
+                                ),
+                            )
+                            .with_needle(link),
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the page link and fragment targeted by the link, or `None` for external links.
+fn resolve(base_url: &str, page_link: &str, link: &str) -> Option<(String, Option<String>)> {
+    let base_url = base_url.trim_end_matches('/');
+    let link = match link.strip_prefix(base_url) {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '#', '?']) => rest,
+        _ => link,
+    };
+
+    if link.is_empty() || link == "#" || link.starts_with("//") {
+        return None;
+    }
+    if let Some(colon) = link.find(':') {
+        if !link[..colon].contains(['/', '?', '#']) {
+            return None;
+        }
+    }
+
+    let base = Url::parse(RESOLVE_BASE)
+        .and_then(|base| base.join(page_link))
+        .ok()?;
+    let url = base.join(link).ok()?;
+
+    let path = url.path().trim_matches('/').to_owned();
+    let fragment = url
+        .fragment()
+        .filter(|f| !f.is_empty())
+        .map(|f| f.to_owned());
+    Some((path, fragment))
+}
+
+fn is_static_file(project: &Path, path: &str) -> bool {
+    path.starts_with("static/") && project.join(path).is_file()
+}
+
+fn is_server_route(path: &str) -> bool {
+    path.starts_with("api/") || path.starts_with("ws/")
+}
+
+fn for_each_attribute(html: &str, mut f: impl FnMut(&str, &str)) {
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        rest = &rest[name_end..];
+
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+
+            let attribute_end = rest
+                .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '>')
+                .unwrap_or(rest.len());
+            let attribute = &rest[..attribute_end];
+            rest = &rest[attribute_end..];
+
+            let value = match rest.strip_prefix('=') {
+                Some(value) => {
+                    let (value, remaining) = split_value(value);
+                    rest = remaining;
+                    value
+                }
+                None => "",
+            };
+            f(attribute, value);
+        }
+
+        // Contents of these elements are not HTML.
+        if name.eq_ignore_ascii_case("script") || name.eq_ignore_ascii_case("style") {
+            let end = format!("</{}", name.to_ascii_lowercase());
+            rest = rest.find(&end).map_or("", |i| &rest[i..]);
+        }
+    }
+}
+
+fn split_value(value: &str) -> (&str, &str) {
+    match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => match value[1..].find(quote) {
+            Some(end) => (&value[1..end + 1], &value[end + 2..]),
+            None => (&value[1..], ""),
+        },
+        _ => {
+            let end = value
+                .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                .unwrap_or(value.len());
+            (&value[..end], &value[end..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://example.com";
+
+    fn page(link: &str, html: &str) -> Page {
+        Page::scan(link.to_owned(), Vec::new(), html)
+    }
+
+    fn codes(pages: &[Page], routes: &Routes) -> Vec<(&'static str, String)> {
+        let result = check_pages(BASE_URL, Path::new("/nonexistent"), pages, routes);
+        result
+            .errors
+            .iter()
+            .chain(&result.warnings)
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_valid_links() {
+        let pages = [
+            page(
+                "blog/first",
+                r##"<a href="second#intro">Next</a>
+                <a href="/blog/second/">Second</a>
+                <a href="https://example.com/blog/second#intro">Absolute</a>
+                <a href="#top">Top</a><h1 id="top">First</h1>
+                <a href="https://other.org/missing">External</a>
+                <a href="mailto:someone@example.com">Mail</a>
+                <a href="//cdn.example.org/script.js">Cdn</a>
+                <a href="/old">Redirect</a>"##,
+            ),
+            page("blog/second", r#"<h2 id="intro">Intro</h2>"#),
+        ];
+        let mut routes = Routes::default();
+        routes.redirects.insert(
+            "old".to_owned(),
+            crate::routes::Redirect {
+                to: "/blog/second".to_owned(),
+                status: 301,
+            },
+        );
+
+        assert_eq!(codes(&pages, &routes), []);
+    }
+
+    #[test]
+    fn reports_broken_links() {
+        let pages = [
+            page("blog/first", r#"<a href="missing">Missing</a>"#),
+            page("blog/second", ""),
+        ];
+
+        assert_eq!(
+            codes(&pages, &Routes::default()),
+            [(
+                diagnostic::BROKEN_LINK,
+                "Link 'missing' on page '/blog/first' does not exist.".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_broken_anchors() {
+        let pages = [
+            page(
+                "blog/first",
+                r##"<a href="#nowhere">Top</a><a href="second#intro">Next</a>"##,
+            ),
+            page("blog/second", r#"<h2 id="outro">Outro</h2>"#),
+        ];
+
+        assert_eq!(
+            codes(&pages, &Routes::default()),
+            [
+                (
+                    diagnostic::BROKEN_ANCHOR,
+                    "Anchor '#nowhere' of link '#nowhere' on page '/blog/first' does not exist."
+                        .to_owned()
+                ),
+                (
+                    diagnostic::BROKEN_ANCHOR,
+                    "Anchor '#intro' of link 'second#intro' on page '/blog/first' does not exist."
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+}
@@ -1,24 +1,36 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use anyhow::Ok;
 use chrono::{DateTime, Utc};
-use pulldown_cmark::{html, Parser};
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
 use tokio::fs;
 use url::Url;
+use vsm_config::SchemaConfig;
 
-use crate::{content, Context};
+use crate::{
+    content,
+    diagnostic::{self, Diagnostic},
+    Context,
+};
+
+use super::{
+    authors::AUTHORS_FILE, content_variables::ContentVariables, escape_html, history, meta,
+    permalink, schema, search, social_card, structured_data, word_counter,
+};
 
-use super::{content_variables::ContentVariables, word_counter};
+const EXCERPT_MARKER: &str = "<!-- more -->";
+/// Maximal length of descriptions created from excerpts, in characters
+const DESCRIPTION_LENGTH: usize = 160;
 
 pub async fn get_template(context: &Context, path: &Path) -> anyhow::Result<PathBuf> {
     let mut template_path = path.to_path_buf();
     let mut template_found = false;
 
-    let project_directory = Path::new(&context.args.project);
+    let project_directory = Path::new(&context.config.project);
     while let Some(parent) = template_path.parent() {
         if template_path == project_directory {
             break;
@@ -35,10 +47,12 @@ pub async fn get_template(context: &Context, path: &Path) -> anyhow::Result<Path
     }
 
     if !template_found {
-        return Err(anyhow::anyhow!(
-            "Template not found for file '{}'.",
-            path.display()
-        ));
+        return Err(Diagnostic::error(
+            diagnostic::MISSING_TEMPLATE,
+            "Unable to find '_template.html' in any parent directory.",
+        )
+        .with_file(path)
+        .into());
     }
 
     Ok(template_path)
@@ -48,12 +62,31 @@ pub async fn get_template(context: &Context, path: &Path) -> anyhow::Result<Path
 pub struct MarkdownContent {
     pub link: String,
     pub title: String,
+    /// Falls back to the plain text of the excerpt
     pub description: String,
+    /// Rendered content before the `<!-- more -->` marker, or the first paragraph
+    pub excerpt: String,
     pub tags: Vec<String>,
     pub date: DateTime<Utc>,
+    /// The `updated` front matter, author date of the last commit or the publication date
+    pub updated: DateTime<Utc>,
     pub draft: bool,
     pub technical: bool,
     pub difficulty: f64,
+    /// Language of the page from `lang` front matter
+    pub lang: Option<String>,
+    pub image: Option<String>,
+    pub aliases: Vec<String>,
+    /// Collections which include the page regardless of their filters
+    pub collections: Vec<String>,
+    pub series: Option<String>,
+    pub series_order: Option<f64>,
+    /// Links of posts always shown as related
+    pub related: Vec<String>,
+    /// Ids of authors from `author` and `authors` front matter
+    pub authors: Vec<String>,
+    pub word_count: u64,
+    pub text: String,
 }
 
 impl MarkdownContent {
@@ -77,6 +110,16 @@ impl MarkdownContent {
         }
     }
 
+    fn get_element_string_optional(
+        key: &str,
+        md_variables: &HashMap<String, VariableValue>,
+    ) -> anyhow::Result<Option<String>> {
+        match md_variables.contains_key(key) {
+            true => Self::get_element_string(key, md_variables).map(Some),
+            false => Ok(None),
+        }
+    }
+
     fn get_element_string_vec(
         key: &str,
         md_variables: &HashMap<String, VariableValue>,
@@ -98,6 +141,16 @@ impl MarkdownContent {
         }
     }
 
+    fn get_element_string_vec_optional(
+        key: &str,
+        md_variables: &HashMap<String, VariableValue>,
+    ) -> anyhow::Result<Vec<String>> {
+        match md_variables.contains_key(key) {
+            true => Self::get_element_string_vec(key, md_variables),
+            false => Ok(Vec::new()),
+        }
+    }
+
     fn get_element_date(
         key: &str,
         md_variables: &HashMap<String, VariableValue>,
@@ -127,6 +180,16 @@ impl MarkdownContent {
             _ => anyhow::bail!("Variable '{}' is not a number.", key),
         }
     }
+
+    fn get_element_number_optional(
+        key: &str,
+        md_variables: &HashMap<String, VariableValue>,
+    ) -> anyhow::Result<Option<f64>> {
+        match md_variables.contains_key(key) {
+            true => Self::get_element_number(key, md_variables).map(Some),
+            false => Ok(None),
+        }
+    }
 }
 
 pub async fn set_variables(
@@ -135,19 +198,31 @@ pub async fn set_variables(
     variables: &mut ContentVariables,
 ) -> anyhow::Result<MarkdownContent> {
     let mut file_content = fs::read_to_string(path).await?;
-    let md_variables = extract_variables(&mut file_content)?;
+    let md_variables = extract_variables(&mut file_content)
+        .map_err(|error| Diagnostic::from(error).with_file(path))?;
+    let manual_difficulty = md_variables.contains_key("difficulty");
     let process_variables = process_variables(context, path, variables, md_variables);
 
-    let parser = Parser::new(file_content.as_str());
-
-    let mut html = String::new();
-    html::push_html(&mut html, parser);
-
-    let cite_notes = generate_cite_notes(&mut html).await;
+    let (html, cite_notes) = render_html(context, &file_content).await;
     let table_of_contents = generate_table_of_contents(&html).await;
 
     let mut content = process_variables.await?;
-    word_counter::compute_read_time(&file_content, &mut content, variables);
+    let excerpt = create_excerpt(get_parser_options(context), &file_content);
+    if content.description.is_empty() {
+        content.description = shorten(&search::extract_text(context, excerpt));
+        variables.insert("md_description".to_owned(), content.description.clone());
+    }
+    // Excerpts are shown on other pages, so links relative to the post are made absolute.
+    content.excerpt = absolutize_links(&render_html(context, excerpt).await.0, &content.link);
+    variables.insert("md_excerpt".to_owned(), content.excerpt.clone());
+    word_counter::compute_read_time(
+        context,
+        &file_content,
+        manual_difficulty,
+        &mut content,
+        variables,
+    );
+    content.text = search::extract_text(context, &file_content);
 
     variables.insert("md_content".to_owned(), html);
     variables.insert("md_cite_notes".to_owned(), cite_notes);
@@ -160,17 +235,155 @@ pub async fn set_variables(
         table_of_contents.1,
     );
 
-    let mut tags = String::new();
-    for tag in &content.tags {
-        tags.push_str(format!("<a>#<strong>{}</strong></a>", tag).as_str());
-    }
-    variables.insert("md_tags".to_owned(), tags);
+    variables.insert("md_tags".to_owned(), render_tags(&content.tags));
 
     variables.insert("warning".to_owned(), get_draft_info(&content));
 
+    if content.image.is_none() {
+        match social_card::create(context, path, &content).await {
+            std::result::Result::Ok(image) => content.image = image,
+            Err(err) => tracing::error!(
+                "Unable to create social card for '{}': {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+    variables.insert(
+        "md_meta".to_owned(),
+        meta::create_md_meta(context, &content),
+    );
+    variables.insert(
+        "md_json_ld".to_owned(),
+        structured_data::create_md_json_ld(context, &content)?,
+    );
+
     Ok(content)
 }
 
+pub fn get_parser_options(context: &Context) -> Options {
+    let config = &context.config.markdown;
+
+    let mut options = Options::empty();
+    options.set(Options::ENABLE_TABLES, config.tables);
+    options.set(Options::ENABLE_FOOTNOTES, config.footnotes);
+    options.set(Options::ENABLE_STRIKETHROUGH, config.strikethrough);
+    options.set(Options::ENABLE_TASKLISTS, config.tasklists);
+    options.set(Options::ENABLE_SMART_PUNCTUATION, config.smart_punctuation);
+    options.set(
+        Options::ENABLE_HEADING_ATTRIBUTES,
+        config.heading_attributes,
+    );
+    options
+}
+
+/// Renders markdown with cite notes, returns the HTML and the list of cite notes.
+async fn render_html(context: &Context, markdown: &str) -> (String, String) {
+    let mut html = String::new();
+    html::push_html(
+        &mut html,
+        Parser::new_ext(markdown, get_parser_options(context)),
+    );
+
+    let cite_notes = generate_cite_notes(&mut html).await;
+    (html, cite_notes)
+}
+
+pub fn render_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("<a>#<strong>{}</strong></a>", escape_html(tag)))
+        .collect()
+}
+
+/// Markdown before the `<!-- more -->` marker, or the first paragraph.
+fn create_excerpt(options: Options, markdown: &str) -> &str {
+    let mut first_paragraph = None;
+    for (event, range) in Parser::new_ext(markdown, options).into_offset_iter() {
+        match event {
+            Event::Html(html) | Event::InlineHtml(html) if html.trim() == EXCERPT_MARKER => {
+                return &markdown[..range.start];
+            }
+            Event::Start(Tag::Paragraph) if first_paragraph.is_none() => {
+                first_paragraph = Some(range)
+            }
+            _ => (),
+        }
+    }
+
+    first_paragraph.map_or("", |range| &markdown[range])
+}
+
+/// Resolves relative `href` and `src` attributes against the link of the page.
+fn absolutize_links(html: &str, link: &str) -> String {
+    let base = Url::parse("http://localhost/")
+        .and_then(|base| base.join(link))
+        .expect("Links are valid URL paths.");
+
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = [" href=\"", " src=\""]
+        .iter()
+        .filter_map(|attribute| rest.find(attribute).map(|i| i + attribute.len()))
+        .min()
+    {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find('"').unwrap_or(rest.len());
+        let value = &rest[..end];
+
+        let is_relative = !value.starts_with('/')
+            && !value.contains("{{")
+            && value
+                .split(['/', '?', '#'])
+                .next()
+                .is_none_or(|segment| !segment.contains(':'));
+        match base.join(value) {
+            std::result::Result::Ok(url) if is_relative => {
+                result.push_str(url.path());
+                if let Some(query) = url.query() {
+                    result.push('?');
+                    result.push_str(query);
+                }
+                if let Some(fragment) = url.fragment() {
+                    result.push('#');
+                    result.push_str(fragment);
+                }
+            }
+            _ => result.push_str(value),
+        }
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Shortens the text to the length of a description, at a word boundary.
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= DESCRIPTION_LENGTH {
+        return text.to_owned();
+    }
+
+    let mut result = String::new();
+    for word in text.split_whitespace() {
+        if result.chars().count() + word.chars().count() + 1 > DESCRIPTION_LENGTH {
+            break;
+        }
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        result.push_str(word);
+    }
+
+    // The first word alone is too long.
+    if result.is_empty() {
+        result = text.chars().take(DESCRIPTION_LENGTH - 1).collect();
+    }
+
+    result.push('…');
+    result
+}
+
 fn get_draft_info(content: &MarkdownContent) -> String {
     match content.draft {
         true => {
@@ -195,11 +408,22 @@ fn extract_variables(file_content: &mut String) -> anyhow::Result<HashMap<String
 
     let mut result = HashMap::new();
 
+    let first_line = file_content[..start_with_key].matches('\n').count() + 1;
     let variable_text = &file_content[start_with_key..start_with_key + end];
-    for line in variable_text.lines() {
+    for (index, line) in variable_text.lines().enumerate() {
         let mut parts = line.splitn(2, ':');
         if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-            let value = VariableValue::from_str(value)?;
+            let value = VariableValue::from_str(value).map_err(|error| {
+                Diagnostic::error(
+                    diagnostic::FRONT_MATTER,
+                    format!("Invalid value of variable '{}': {}.", key.trim(), error),
+                )
+                .with_span(
+                    first_line + index,
+                    line.find(value.trim()).unwrap_or_default() + 1,
+                    value.trim().chars().count(),
+                )
+            })?;
             result.insert(key.trim().to_owned(), value);
         }
     }
@@ -212,42 +436,133 @@ async fn process_variables(
     context: &Arc<Context>,
     path: &Path,
     variables: &mut ContentVariables,
-    md_variables: HashMap<String, VariableValue>,
+    mut md_variables: HashMap<String, VariableValue>,
 ) -> anyhow::Result<MarkdownContent> {
-    for key in ["title", "description", "date"] {
-        let Some(value) = md_variables.get(key) else {
-            anyhow::bail!("Unable to find markdown variable with key '{}'", key);
-        };
+    schema::apply(context, path, &mut md_variables)?;
+
+    // Fields used by the generator are exposed through `MarkdownContent`, except of these.
+    let builtin = SchemaConfig::builtin();
+    let exposed = md_variables.keys().filter(|key| {
+        ["title", "description", "date"].contains(&key.as_str())
+            || !builtin.fields.contains_key(*key)
+    });
+    for key in exposed {
+        let value = &md_variables[key];
         let key = format!("md_{}", key);
 
         match value {
             VariableValue::String(str) => variables.insert(key, str.to_owned()),
-            VariableValue::Array(_array) => {
-                tracing::warn!("Array variables are not supported yet.");
+            VariableValue::Array(array) => {
+                let values = array.iter().filter_map(|value| match value {
+                    VariableValue::String(str) => Some(str.as_str()),
+                    _ => None,
+                });
+                variables.insert(key, values.collect::<Vec<_>>().join(", "))
             }
-            VariableValue::Date(date) => variables.insert(
-                key,
-                format!(
-                    r#"{}<div class="tooltip">{}</div>"#,
-                    date.format("%e %B %Y"),
-                    date.format("%A, %e %B %Y %H:%M:%S UTC")
-                ),
-            ),
+            VariableValue::Date(date) => variables.insert(key, format_date(date)),
             VariableValue::Bool(bool) => variables.insert(key, bool.to_string()),
             VariableValue::Number(number) => variables.insert(key, number.to_string()),
         };
     }
 
-    Ok(MarkdownContent {
-        link: context.get_file_link(path),
+    let mut content = MarkdownContent {
+        link: String::new(),
         title: MarkdownContent::get_element_string("title", &md_variables)?,
-        description: MarkdownContent::get_element_string("description", &md_variables)?,
+        description: MarkdownContent::get_element_string_optional("description", &md_variables)?
+            .unwrap_or_default(),
+        excerpt: String::new(),
         tags: MarkdownContent::get_element_string_vec("tags", &md_variables)?,
         date: MarkdownContent::get_element_date("date", &md_variables)?,
+        updated: DateTime::default(),
         draft: MarkdownContent::get_element_bool("draft", &md_variables)?,
         technical: MarkdownContent::get_element_bool("technical", &md_variables)?,
         difficulty: MarkdownContent::get_element_number("difficulty", &md_variables)?,
-    })
+        lang: MarkdownContent::get_element_string_optional("lang", &md_variables)?,
+        image: MarkdownContent::get_element_string_optional("image", &md_variables)?,
+        aliases: MarkdownContent::get_element_string_vec_optional("aliases", &md_variables)?,
+        collections: MarkdownContent::get_element_string_vec_optional(
+            "collections",
+            &md_variables,
+        )?,
+        series: MarkdownContent::get_element_string_optional("series", &md_variables)?,
+        series_order: MarkdownContent::get_element_number_optional("series_order", &md_variables)?,
+        related: MarkdownContent::get_element_string_vec_optional("related", &md_variables)?,
+        authors: MarkdownContent::get_element_string_optional("author", &md_variables)?
+            .into_iter()
+            .chain(MarkdownContent::get_element_string_vec_optional(
+                "authors",
+                &md_variables,
+            )?)
+            .collect(),
+        word_count: 0,
+        text: String::new(),
+    };
+
+    let slug = MarkdownContent::get_element_string_optional("slug", &md_variables)?;
+    let permalink = MarkdownContent::get_element_string_optional("permalink", &md_variables)?;
+    content.link = permalink::resolve(
+        &context.get_file_link(path),
+        &content,
+        slug.as_deref(),
+        permalink.as_deref(),
+    )
+    .map_err(|error| {
+        let mut diagnostic = Diagnostic::from(error);
+        diagnostic.locate([path]);
+        diagnostic.or_file(path)
+    })?;
+    variables.insert("link".to_owned(), content.link.clone());
+
+    let revisions = context.history.get(&context.config, path);
+    content.updated = match md_variables.get("updated") {
+        Some(VariableValue::Date(updated)) => *updated,
+        _ => revisions
+            .first()
+            .map_or(content.date, |revision| revision.date),
+    };
+    variables.insert("md_updated".to_owned(), format_date(&content.updated));
+    variables.insert("md_history".to_owned(), history::render(revisions));
+    variables.insert("md_revisions".to_owned(), revisions.len().to_string());
+
+    let mut seen = HashSet::new();
+    content.authors.retain(|id| seen.insert(id.clone()));
+    if let Some(id) = content
+        .authors
+        .iter()
+        .find(|id| context.authors.get(id).is_none())
+    {
+        let mut diagnostic = Diagnostic::error(
+            diagnostic::FRONT_MATTER,
+            format!("Author '{}' is not defined in '{}'.", id, AUTHORS_FILE),
+        )
+        .with_needle(id);
+        diagnostic.locate([path]);
+        return Err(diagnostic.or_file(path).into());
+    }
+    variables.insert(
+        "md_authors".to_owned(),
+        context.authors.render(&content.authors),
+    );
+    variables.insert(
+        "md_author".to_owned(),
+        content
+            .authors
+            .iter()
+            .filter_map(|id| context.authors.get(id))
+            .map(|author| escape_html(&author.name))
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+
+    Ok(content)
+}
+
+fn format_date(date: &DateTime<Utc>) -> String {
+    format!(
+        r#"{}<div class="tooltip">{}</div>"#,
+        date.format("%e %B %Y"),
+        date.format("%A, %e %B %Y %H:%M:%S UTC")
+    )
 }
 
 async fn generate_table_of_contents(html: &str) -> (String, String) {
@@ -439,8 +754,8 @@ fn get_description_of_cite_note(html: &str, bracket_index: usize) -> Option<&str
     }
 }
 
-#[derive(Debug)]
-enum VariableValue {
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariableValue {
     String(String),
     Bool(bool),
     Number(f64),
@@ -453,7 +768,10 @@ impl VariableValue {
         let value = value.trim();
         if value.starts_with('[') {
             let mut array = Vec::new();
-            for value in value[1..value.len() - 1].split(',') {
+            for value in value[1..value.len() - 1]
+                .split(',')
+                .filter(|value| !value.trim().is_empty())
+            {
                 array.push(Self::from_str(value)?);
             }
 
@@ -482,3 +800,72 @@ impl VariableValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpt_ends_at_marker() {
+        let markdown = "First paragraph.\n\nSecond paragraph.\n\n<!-- more -->\n\nRest.";
+        assert_eq!(
+            create_excerpt(Options::empty(), markdown),
+            "First paragraph.\n\nSecond paragraph.\n\n"
+        );
+    }
+
+    #[test]
+    fn excerpt_ignores_marker_in_code() {
+        let markdown = "Intro `<!-- more -->` text.\n\n```html\n<!-- more -->\n```\n\nRest.";
+        assert_eq!(
+            create_excerpt(Options::empty(), markdown),
+            "Intro `<!-- more -->` text.\n"
+        );
+    }
+
+    #[test]
+    fn excerpt_falls_back_to_first_paragraph() {
+        let markdown = "# Title\n\nFirst paragraph.\n\nSecond paragraph.";
+        assert_eq!(
+            create_excerpt(Options::empty(), markdown),
+            "First paragraph.\n"
+        );
+        assert_eq!(create_excerpt(Options::empty(), "# Title"), "");
+    }
+
+    #[test]
+    fn absolutizes_relative_links() {
+        let html = concat!(
+            r#"<a href="other">a</a><img src="img/pic.png"><a href="../up?x=1">b</a>"#,
+            r##"<a href="#section">c</a><a href="/root">d</a><a href="https://x.org/a">e</a>"##,
+            r#"<a href="mailto:a@b.c">f</a><a href="{{link}}">g</a>"#
+        );
+        assert_eq!(
+            absolutize_links(html, "blog/post"),
+            concat!(
+                r#"<a href="/blog/other">a</a><img src="/blog/img/pic.png"><a href="/up?x=1">b</a>"#,
+                r##"<a href="/blog/post#section">c</a><a href="/root">d</a>"##,
+                r#"<a href="https://x.org/a">e</a><a href="mailto:a@b.c">f</a>"#,
+                r#"<a href="{{link}}">g</a>"#
+            )
+        );
+    }
+
+    #[test]
+    fn shortens_at_word_boundary() {
+        assert_eq!(shorten("Short text."), "Short text.");
+
+        let text = "word ".repeat(50);
+        let short = shorten(&text);
+        assert!(short.ends_with("word…"));
+        assert!(short.chars().count() <= DESCRIPTION_LENGTH);
+    }
+
+    #[test]
+    fn shortens_long_first_word() {
+        let text = "a".repeat(200);
+        let short = shorten(&text);
+        assert_eq!(short.chars().count(), DESCRIPTION_LENGTH);
+        assert!(short.starts_with("aaa") && short.ends_with('…'));
+    }
+}
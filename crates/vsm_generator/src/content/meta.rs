@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::Context;
+
+use super::{escape_html, markdown::MarkdownContent};
+
+pub fn create_md_meta(context: &Context, content: &MarkdownContent) -> String {
+    let mut meta = String::new();
+
+    let url = escape_html(&context.get_absolute_url(&content.link));
+    let title = escape_html(&content.title);
+    let description = escape_html(&content.description);
+
+    push_tag(&mut meta, "link", "rel", "canonical", "href", &url);
+    push_tag(
+        &mut meta,
+        "meta",
+        "name",
+        "description",
+        "content",
+        &description,
+    );
+
+    push_property(&mut meta, "og:type", "article");
+    push_property(
+        &mut meta,
+        "og:site_name",
+        &escape_html(&context.config.site.title),
+    );
+    push_property(&mut meta, "og:url", &url);
+    push_property(&mut meta, "og:title", &title);
+    push_property(&mut meta, "og:description", &description);
+    push_property(
+        &mut meta,
+        "article:published_time",
+        &content.date.to_rfc3339(),
+    );
+    push_property(
+        &mut meta,
+        "article:modified_time",
+        &content.updated.to_rfc3339(),
+    );
+    for tag in &content.tags {
+        push_property(&mut meta, "article:tag", &escape_html(tag));
+    }
+
+    let image = content
+        .image
+        .as_ref()
+        .map(|image| escape_html(&context.get_absolute_url(image)));
+    match &image {
+        Some(image) => {
+            push_property(&mut meta, "og:image", image);
+            push_name(&mut meta, "twitter:card", "summary_large_image");
+            push_name(&mut meta, "twitter:image", image);
+        }
+        None => push_name(&mut meta, "twitter:card", "summary"),
+    }
+    push_name(&mut meta, "twitter:title", &title);
+    push_name(&mut meta, "twitter:description", &description);
+
+    meta
+}
+
+fn push_property(meta: &mut String, property: &str, content: &str) {
+    push_tag(meta, "meta", "property", property, "content", content);
+}
+
+fn push_name(meta: &mut String, name: &str, content: &str) {
+    push_tag(meta, "meta", "name", name, "content", content);
+}
+
+fn push_tag(
+    meta: &mut String,
+    element: &str,
+    key: &str,
+    key_value: &str,
+    attribute: &str,
+    value: &str,
+) {
+    write!(
+        meta,
+        r#"<{element} {key}="{key_value}" {attribute}="{value}" />"#
+    )
+    .expect("Unable to write meta tag.");
+}
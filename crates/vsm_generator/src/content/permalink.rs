@@ -0,0 +1,154 @@
+use crate::diagnostic::{self, Diagnostic};
+
+use super::{markdown::MarkdownContent, slugify};
+
+/// Resolves the link of the page from `slug` and `permalink` front matter. The permalink may
+/// contain `:year`, `:month`, `:day`, `:slug`, `:title` and `:section` placeholders.
+pub fn resolve(
+    file_link: &str,
+    content: &MarkdownContent,
+    slug: Option<&str>,
+    permalink: Option<&str>,
+) -> anyhow::Result<String> {
+    let (section, file_slug) = match file_link.rsplit_once('/') {
+        Some((section, file_slug)) => (section, file_slug),
+        None => ("", file_link),
+    };
+    let slug = match slug {
+        Some(slug) => slugify(slug),
+        None => file_slug.to_owned(),
+    };
+
+    let link = match permalink {
+        Some(permalink) => {
+            let mut link = Vec::new();
+            for segment in permalink.split('/').filter(|s| !s.is_empty()) {
+                let Some(placeholder) = segment.strip_prefix(':') else {
+                    link.push(segment.to_owned());
+                    continue;
+                };
+
+                link.push(match placeholder {
+                    "year" => content.date.format("%Y").to_string(),
+                    "month" => content.date.format("%m").to_string(),
+                    "day" => content.date.format("%d").to_string(),
+                    "slug" => slug.clone(),
+                    "title" => slugify(&content.title),
+                    "section" => section.to_owned(),
+                    _ => {
+                        return Err(Diagnostic::error(
+                            diagnostic::INVALID_PERMALINK,
+                            format!("Unknown permalink placeholder ':{}'.", placeholder),
+                        )
+                        .with_needle(segment)
+                        .into())
+                    }
+                });
+            }
+
+            link.retain(|s| !s.is_empty());
+            link.join("/")
+        }
+        None if section.is_empty() => slug,
+        None => format!("{section}/{slug}"),
+    };
+
+    validate(&link)?;
+    match link == "index" {
+        true => Ok(String::new()),
+        false => Ok(link),
+    }
+}
+
+fn validate(link: &str) -> anyhow::Result<()> {
+    let invalid =
+        |message: String| Err(Diagnostic::error(diagnostic::INVALID_PERMALINK, message).into());
+
+    for segment in link.split('/') {
+        if segment == "." || segment == ".." {
+            return invalid(format!("Permalink '/{}' contains relative segments.", link));
+        }
+        if segment.contains(['?', '#', '\\']) {
+            return invalid(format!(
+                "Permalink '/{}' contains characters not allowed in paths.",
+                link
+            ));
+        }
+    }
+
+    // Paths with extensions are treated as files by `vsm` and never served as pages.
+    if link.rsplit('/').next().is_some_and(|s| s.contains('.')) {
+        return invalid(format!("Permalink '/{}' must not have an extension.", link));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn content() -> MarkdownContent {
+        MarkdownContent {
+            title: "Hello, World!".to_owned(),
+            date: Utc.with_ymd_and_hms(2024, 3, 7, 10, 0, 0).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn defaults_to_file_link() {
+        let content = content();
+        assert_eq!(
+            resolve("blog/first", &content, None, None).unwrap(),
+            "blog/first"
+        );
+        assert_eq!(resolve("index", &content, None, None).unwrap(), "");
+    }
+
+    #[test]
+    fn replaces_slug_in_section() {
+        let content = content();
+        assert_eq!(
+            resolve("blog/first", &content, Some("Other Name"), None).unwrap(),
+            "blog/other-name"
+        );
+        assert_eq!(
+            resolve("first", &content, Some("other"), None).unwrap(),
+            "other"
+        );
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        let content = content();
+        assert_eq!(
+            resolve(
+                "blog/first",
+                &content,
+                None,
+                Some("/:section/:year/:month/:day/:slug/")
+            )
+            .unwrap(),
+            "blog/2024/03/07/first"
+        );
+        assert_eq!(
+            resolve("first", &content, None, Some(":section/:title")).unwrap(),
+            "hello-world"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_permalinks() {
+        let content = content();
+        for permalink in [":unknown", "../:slug", "a?b", ":slug.html"] {
+            assert!(
+                resolve("blog/first", &content, None, Some(permalink)).is_err(),
+                "{}",
+                permalink
+            );
+        }
+    }
+}
@@ -7,8 +7,11 @@ use super::{
     markdown::{self, MarkdownContent},
 };
 
+#[derive(Debug)]
 pub struct PreliminaryAnalysisOutput {
     pub path: PathBuf,
+    /// Link of the page, which for markdown can be changed by `slug` and `permalink`
+    pub link: String,
     pub template_path: PathBuf,
     pub variables: ContentVariables,
     pub content: Option<MarkdownContent>,
@@ -35,61 +38,16 @@ pub async fn analyze_file(
         _ => path.clone(),
     };
 
+    let link = match &content {
+        Some(content) => content.link.clone(),
+        None => context.get_file_link(&path),
+    };
+
     Ok(PreliminaryAnalysisOutput {
         path,
+        link,
         template_path,
         variables,
         content,
     })
 }
-
-pub async fn create_md_post_list(
-    outputs: &[Arc<PreliminaryAnalysisOutput>],
-) -> anyhow::Result<String> {
-    let mut result = String::new();
-
-    let mut vec = outputs
-        .iter()
-        .filter_map(|v| match &v.content {
-            Some(c) => Some(c),
-            None => None,
-        })
-        .filter(|v| !v.draft)
-        .collect::<Vec<_>>();
-    vec.sort_by(|a, b| b.date.cmp(&a.date));
-
-    for content in vec {
-        result.push_str(
-            format!(
-                r#"<div class="post-list">
-                    <div class="post-list-top">
-                        <a href="{}">{}</a>
-                        <div class="tooltip-wrapper">
-                            {}
-                            <div class="tooltip">{}</div>
-                        </div>
-                    </div>
-                    <p>{}</p>
-                    <div class="post-list-tags">"#,
-                content.link,
-                content.title,
-                content.date.format("%e&nbsp;%B&nbsp;%Y"),
-                content.date.format("%A, %e %B %Y %H:%M:%S UTC"),
-                content.description
-            )
-            .as_str(),
-        );
-
-        for tag in &content.tags {
-            result.push_str(format!("<a>#{}</a>", tag).as_str());
-        }
-
-        result.push_str("</div></div>");
-    }
-
-    if result.is_empty() {
-        result.push_str("<p>Unfortunately, page still don't have any posts :(</p>");
-    }
-
-    Ok(result)
-}
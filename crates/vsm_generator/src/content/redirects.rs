@@ -0,0 +1,233 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    diagnostic::{self, Diagnostic},
+    output,
+    routes::{self, Redirect, Routes, ROUTES_FILE},
+    Context, Mode,
+};
+
+use super::{preliminary_analysis::PreliminaryAnalysisOutput, ContentResult};
+
+/// Project level file with redirects, one `<from> <to> [301|302|307|308]` per line, lines starting
+/// with `#` are comments.
+pub const REDIRECTS_FILE: &str = "redirects.txt";
+
+const DEFAULT_STATUS: u16 = 301;
+const ALLOWED_STATUSES: [u16; 4] = [301, 302, 307, 308];
+
+/// Creates routes with redirects from aliases and the redirects file, `links` are links of all
+/// emitted pages.
+pub async fn create_routes(
+    context: &Arc<Context>,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+    links: &[String],
+    result: &mut ContentResult,
+) -> anyhow::Result<Routes> {
+    let pages = links.iter().cloned().collect::<HashSet<_>>();
+
+    let mut routes = Routes {
+        pages: pages
+            .iter()
+            .filter(|link| !routes::is_error_page(link))
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    routes.pages.sort();
+
+    for output in outputs {
+        let Some(content) = &output.content else {
+            continue;
+        };
+
+        for alias in &content.aliases {
+            let redirect = Redirect {
+                to: format!("/{}", content.link),
+                status: DEFAULT_STATUS,
+            };
+            if let Some(mut diagnostic) = insert(&mut routes, &pages, alias, redirect) {
+                diagnostic = diagnostic.with_needle(alias);
+                diagnostic.locate([output.path.as_path()]);
+                result.push(diagnostic.or_file(&output.path));
+            }
+        }
+    }
+
+    let path = context.config.project.join(REDIRECTS_FILE);
+    if path.exists() {
+        let data = tokio::fs::read_to_string(&path).await?;
+        for diagnostic in insert_file(&mut routes, &pages, &data) {
+            result.push(diagnostic.with_file(&path));
+        }
+    }
+
+    if context.mode == Mode::Build {
+        let output_path = context.output.join(ROUTES_FILE);
+        output::write(
+            context,
+            &output_path,
+            &serde_json::to_vec_pretty(&routes)?,
+            path.exists().then_some(path.as_path()),
+        )
+        .await?;
+    }
+
+    tracing::trace!("Created {} redirects.", routes.redirects.len());
+    Ok(routes)
+}
+
+/// Inserts redirects from the redirects file, returns diagnostics pointing at invalid lines.
+fn insert_file(routes: &mut Routes, pages: &HashSet<String>, data: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (index, line) in data.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let column = line.len() - line.trim_start().len() + 1;
+        let length = line.trim().chars().count();
+        let diagnostic = match parse_line(line) {
+            Ok((from, redirect)) => insert(routes, pages, from, redirect),
+            Err(message) => Some(Diagnostic::error(diagnostic::INVALID_REDIRECT, message)),
+        };
+        if let Some(diagnostic) = diagnostic {
+            diagnostics.push(diagnostic.with_span(index + 1, column, length));
+        }
+    }
+
+    diagnostics
+}
+
+fn parse_line(line: &str) -> Result<(&str, Redirect), String> {
+    let parts = line.split_whitespace().collect::<Vec<_>>();
+    let (from, to, status) = match parts.as_slice() {
+        [from, to] => (*from, *to, DEFAULT_STATUS),
+        [from, to, status] => match status.parse::<u16>() {
+            Ok(status) if ALLOWED_STATUSES.contains(&status) => (*from, *to, status),
+            _ => {
+                return Err(format!(
+                    "Invalid redirect status '{}', expected one of {:?}.",
+                    status, ALLOWED_STATUSES
+                ))
+            }
+        },
+        _ => return Err("Expected redirect in format '<from> <to> [status]'.".to_owned()),
+    };
+
+    Ok((
+        from,
+        Redirect {
+            to: to.to_owned(),
+            status,
+        },
+    ))
+}
+
+/// Returns a diagnostic when the redirect can not be added.
+fn insert(
+    routes: &mut Routes,
+    pages: &HashSet<String>,
+    from: &str,
+    redirect: Redirect,
+) -> Option<Diagnostic> {
+    let key = routes::normalize(from);
+    if pages.contains(&key) {
+        return Some(Diagnostic::error(
+            diagnostic::REDIRECT_COLLISION,
+            format!("Redirect from '/{}' collides with an existing page.", key),
+        ));
+    }
+    if routes::normalize(&redirect.to) == key {
+        return Some(Diagnostic::error(
+            diagnostic::INVALID_REDIRECT,
+            format!("Redirect from '/{}' points to itself.", key),
+        ));
+    }
+
+    match routes.redirects.get(&key) {
+        Some(existing) if *existing != redirect => Some(Diagnostic::error(
+            diagnostic::REDIRECT_COLLISION,
+            format!(
+                "Redirect from '/{}' to '{}' collides with redirect to '{}'.",
+                key, redirect.to, existing.to
+            ),
+        )),
+        _ => {
+            routes.redirects.insert(key, redirect);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages() -> HashSet<String> {
+        ["blog/post".to_owned()].into_iter().collect()
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let mut routes = Routes::default();
+        let data = "# Old links\n\n  # indented comment\n/old /new\n   \n/temp /new 302\n";
+
+        assert!(insert_file(&mut routes, &pages(), data).is_empty());
+        assert_eq!(routes.redirects.len(), 2);
+        assert_eq!(
+            routes.redirects["old"],
+            Redirect {
+                to: "/new".to_owned(),
+                status: 301
+            }
+        );
+        assert_eq!(routes.redirects["temp"].status, 302);
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let mut routes = Routes::default();
+        let data = "/old\n  /old /new 200\n/a /b /c /d\n/self /self/\n/ok /new\n";
+
+        let diagnostics = insert_file(&mut routes, &pages(), data);
+        let spans = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.line, diagnostic.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                (diagnostic::INVALID_REDIRECT, Some(1), Some(1)),
+                (diagnostic::INVALID_REDIRECT, Some(2), Some(3)),
+                (diagnostic::INVALID_REDIRECT, Some(3), Some(1)),
+                (diagnostic::INVALID_REDIRECT, Some(4), Some(1)),
+            ]
+        );
+        assert_eq!(routes.redirects.keys().collect::<Vec<_>>(), ["ok"]);
+    }
+
+    #[test]
+    fn reports_collisions() {
+        let mut routes = Routes::default();
+        let redirect = |to: &str| Redirect {
+            to: to.to_owned(),
+            status: DEFAULT_STATUS,
+        };
+
+        let diagnostic = insert(&mut routes, &pages(), "/blog/post/", redirect("/new"));
+        assert_eq!(
+            diagnostic.map(|diagnostic| diagnostic.code),
+            Some(diagnostic::REDIRECT_COLLISION)
+        );
+
+        assert!(insert(&mut routes, &pages(), "/old", redirect("/new")).is_none());
+        assert!(insert(&mut routes, &pages(), "old/", redirect("/new")).is_none());
+        let diagnostic = insert(&mut routes, &pages(), "/old", redirect("/other"));
+        assert_eq!(
+            diagnostic.map(|diagnostic| diagnostic.code),
+            Some(diagnostic::REDIRECT_COLLISION)
+        );
+        assert_eq!(routes.redirects["old"].to, "/new");
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use vsm_config::DEFAULT_COLLECTION;
+
+use crate::{
+    diagnostic::{self, Diagnostic},
+    routes, Context,
+};
+
+use super::{
+    collections, escape_html, preliminary_analysis::PreliminaryAnalysisOutput, series::Navigation,
+    ContentResult,
+};
+
+/// Weight of tag overlap in the score, the rest is text similarity.
+const TAG_WEIGHT: f64 = 0.5;
+const MIN_WORD_LENGTH: usize = 3;
+
+/// Fills `related_posts` of the navigation with pinned posts followed by posts with the highest
+/// tag overlap and TF-IDF similarity of their text.
+pub fn create_related_posts(
+    context: &Arc<Context>,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+    navigation: &mut HashMap<String, Navigation>,
+    result: &mut ContentResult,
+) {
+    let mut config = context
+        .config
+        .collections
+        .get(DEFAULT_COLLECTION)
+        .cloned()
+        .unwrap_or_default();
+    config.limit = None;
+    let posts = collections::collect_pages(context, DEFAULT_COLLECTION, &config, outputs);
+    let by_link = posts
+        .iter()
+        .map(|post| (post.link.as_str(), post))
+        .collect::<HashMap<_, _>>();
+
+    let vectors = tf_idf(&posts);
+    for (index, post) in posts.iter().enumerate() {
+        let content = post.content.as_ref().unwrap();
+        let mut related = Vec::new();
+
+        for pinned in &content.related {
+            match by_link.get(routes::normalize(pinned).as_str()) {
+                Some(pinned) if pinned.link != post.link => related.push(*pinned),
+                Some(_) => (),
+                None => {
+                    let mut diagnostic = Diagnostic::warning(
+                        diagnostic::FRONT_MATTER,
+                        format!("Related post '{}' does not exist.", pinned),
+                    )
+                    .with_needle(pinned);
+                    diagnostic.locate([post.path.as_path()]);
+                    result.push(diagnostic.or_file(&post.path));
+                }
+            }
+        }
+
+        let related = rank(
+            &posts,
+            &vectors,
+            index,
+            related,
+            context.config.related.count,
+        );
+        navigation
+            .entry(post.link.clone())
+            .or_default()
+            .related_posts = render(context, &related, result);
+    }
+}
+
+/// Returns up to `count` posts related to the post at `index`, pinned posts first, followed by the
+/// posts with the highest score.
+fn rank<'a>(
+    posts: &'a [Arc<PreliminaryAnalysisOutput>],
+    vectors: &[HashMap<String, f64>],
+    index: usize,
+    mut related: Vec<&'a Arc<PreliminaryAnalysisOutput>>,
+    count: usize,
+) -> Vec<&'a Arc<PreliminaryAnalysisOutput>> {
+    let tags = posts[index]
+        .content
+        .as_ref()
+        .unwrap()
+        .tags
+        .iter()
+        .collect::<HashSet<_>>();
+    let mut scored = posts
+        .iter()
+        .enumerate()
+        .filter(|(other, candidate)| {
+            *other != index && !related.iter().any(|r| r.link == candidate.link)
+        })
+        .map(|(other, candidate)| {
+            let candidate_tags = candidate.content.as_ref().unwrap().tags.iter();
+            let candidate_tags = candidate_tags.collect::<HashSet<_>>();
+            let union = tags.union(&candidate_tags).count();
+            let overlap = match union {
+                0 => 0.0,
+                _ => tags.intersection(&candidate_tags).count() as f64 / union as f64,
+            };
+
+            let similarity = cosine(&vectors[index], &vectors[other]);
+            (
+                TAG_WEIGHT * overlap + (1.0 - TAG_WEIGHT) * similarity,
+                candidate,
+            )
+        })
+        .filter(|(score, _)| *score > 0.0)
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    related.extend(scored.into_iter().map(|(_, candidate)| candidate));
+    related.truncate(count);
+    related
+}
+
+fn render(
+    context: &Arc<Context>,
+    posts: &[&Arc<PreliminaryAnalysisOutput>],
+    result: &mut ContentResult,
+) -> String {
+    if posts.is_empty() {
+        return String::new();
+    }
+
+    let Some(template) = context.templates.get(&context.config.related.template) else {
+        let mut result = r#"<ul class="related-posts">"#.to_owned();
+        for post in posts {
+            let content = post.content.as_ref().unwrap();
+            result.push_str(&format!(
+                r#"<li><a href="/{}">{}</a></li>"#,
+                escape_html(&post.link),
+                escape_html(&content.title)
+            ));
+        }
+        result.push_str("</ul>");
+        return result;
+    };
+
+    posts
+        .iter()
+        .map(|post| collections::render_item(context, &template.data, post, result))
+        .collect()
+}
+
+fn tf_idf(posts: &[Arc<PreliminaryAnalysisOutput>]) -> Vec<HashMap<String, f64>> {
+    let frequencies = posts
+        .iter()
+        .map(|post| {
+            let mut frequencies = HashMap::<String, f64>::new();
+            let text = &post.content.as_ref().unwrap().text;
+            for word in text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| word.chars().count() >= MIN_WORD_LENGTH)
+            {
+                *frequencies.entry(word.to_lowercase()).or_default() += 1.0;
+            }
+            frequencies
+        })
+        .collect::<Vec<_>>();
+
+    let mut document_frequencies = HashMap::<&str, f64>::new();
+    for frequencies in &frequencies {
+        for word in frequencies.keys() {
+            *document_frequencies.entry(word).or_default() += 1.0;
+        }
+    }
+
+    let count = posts.len() as f64;
+    frequencies
+        .iter()
+        .map(|frequencies| {
+            frequencies
+                .iter()
+                .map(|(word, frequency)| {
+                    let idf = (count / document_frequencies[word.as_str()]).ln();
+                    (word.clone(), frequency * idf)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot = a
+        .iter()
+        .filter_map(|(word, value)| b.get(word).map(|other| value * other))
+        .sum::<f64>();
+    let length = |v: &HashMap<String, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+
+    match length(a) * length(b) {
+        0.0 => 0.0,
+        lengths => dot / lengths,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::{content_variables::ContentVariables, markdown::MarkdownContent};
+
+    fn post(link: &str, tags: &[&str], text: &str) -> Arc<PreliminaryAnalysisOutput> {
+        Arc::new(PreliminaryAnalysisOutput {
+            path: PathBuf::from(format!("{}.md", link)),
+            link: link.to_owned(),
+            template_path: PathBuf::new(),
+            variables: ContentVariables::new(),
+            content: Some(MarkdownContent {
+                link: link.to_owned(),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                text: text.to_owned(),
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn links(posts: &[&Arc<PreliminaryAnalysisOutput>]) -> Vec<String> {
+        posts.iter().map(|post| post.link.clone()).collect()
+    }
+
+    fn posts() -> Vec<Arc<PreliminaryAnalysisOutput>> {
+        vec![
+            post("post", &["rust", "web"], "Serving pages with axum handlers"),
+            post("tagged", &["rust", "web"], "Generating images of cards"),
+            post("similar", &[], "Serving pages with hyper handlers"),
+            post("unrelated", &["cooking"], "Baking bread at home"),
+        ]
+    }
+
+    #[test]
+    fn prefers_tag_overlap_over_text_similarity() {
+        let posts = posts();
+        let vectors = tf_idf(&posts);
+        assert!(cosine(&vectors[0], &vectors[2]) > cosine(&vectors[0], &vectors[1]));
+
+        let related = rank(&posts, &vectors, 0, Vec::new(), 10);
+        assert_eq!(links(&related), ["tagged", "similar"]);
+    }
+
+    #[test]
+    fn keeps_pinned_posts_first() {
+        let posts = posts();
+        let vectors = tf_idf(&posts);
+
+        let related = rank(&posts, &vectors, 0, vec![&posts[3], &posts[2]], 10);
+        assert_eq!(links(&related), ["unrelated", "similar", "tagged"]);
+
+        let related = rank(&posts, &vectors, 0, vec![&posts[3]], 1);
+        assert_eq!(links(&related), ["unrelated"]);
+    }
+
+    #[test]
+    fn computes_cosine_similarity() {
+        let a = HashMap::from([("a".to_owned(), 1.0), ("b".to_owned(), 1.0)]);
+        let b = HashMap::from([("a".to_owned(), 2.0), ("b".to_owned(), 2.0)]);
+        let c = HashMap::from([("c".to_owned(), 1.0)]);
+        assert!((cosine(&a, &b) - 1.0).abs() < 1e-9);
+        assert_eq!(cosine(&a, &c), 0.0);
+        assert_eq!(cosine(&a, &HashMap::new()), 0.0);
+    }
+}
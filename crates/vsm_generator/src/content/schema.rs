@@ -0,0 +1,300 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use vsm_config::{FieldConfig, FieldType, FieldValue};
+
+use crate::{
+    diagnostic::{self, Diagnostic, Diagnostics},
+    Context,
+};
+
+use super::{collections, markdown::VariableValue};
+
+/// Validates front matter against the project schema and schemas of collections including the page,
+/// and fills in default values of missing fields.
+pub fn apply(
+    context: &Context,
+    path: &Path,
+    md_variables: &mut HashMap<String, VariableValue>,
+) -> anyhow::Result<()> {
+    let strings = |key: &str| match md_variables.get(key) {
+        Some(VariableValue::Array(array)) => array
+            .iter()
+            .filter_map(|value| match value {
+                VariableValue::String(str) => Some(str.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let (tags, listed) = (strings("tags"), strings("collections"));
+
+    let mut fields = context.config.schema.fields.clone();
+    for (name, collection) in &context.config.collections {
+        if collections::includes(context, name, collection, path, &tags, &listed) {
+            fields.extend(collection.schema.fields.clone());
+        }
+    }
+
+    let diagnostics = apply_fields(path, &fields, md_variables);
+    match diagnostics.is_empty() {
+        true => Ok(()),
+        false => Err(Diagnostics(diagnostics).into()),
+    }
+}
+
+/// Fills in defaults and returns located diagnostics of all violations of the fields.
+fn apply_fields(
+    path: &Path,
+    fields: &BTreeMap<String, FieldConfig>,
+    md_variables: &mut HashMap<String, VariableValue>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (key, field) in fields {
+        let Some(value) = md_variables.get(key) else {
+            if field.required {
+                let mut diagnostic = Diagnostic::error(
+                    diagnostic::FRONT_MATTER,
+                    format!("Missing required front matter field '{}'.", key),
+                )
+                .with_needle("---");
+                diagnostic.locate([path]);
+                diagnostics.push(diagnostic.or_file(path));
+                continue;
+            }
+            if let Some(default) = &field.default {
+                match convert(default, field.kind) {
+                    Ok(default) => {
+                        md_variables.insert(key.clone(), default);
+                    }
+                    Err(error) => diagnostics.push(
+                        Diagnostic::error(
+                            diagnostic::FRONT_MATTER,
+                            format!("Invalid default value of field '{}': {}.", key, error),
+                        )
+                        .with_file(path),
+                    ),
+                }
+            }
+            continue;
+        };
+
+        for message in validate(key, value, field) {
+            let mut diagnostic = Diagnostic::error(diagnostic::FRONT_MATTER, message)
+                .with_needle(&format!("{}:", key));
+            diagnostic.locate([path]);
+            diagnostics.push(diagnostic.or_file(path));
+        }
+    }
+
+    diagnostics
+}
+
+/// Returns messages of the type mismatch or of every value which is not allowed.
+fn validate(key: &str, value: &VariableValue, field: &FieldConfig) -> Vec<String> {
+    let matches = match (field.kind, value) {
+        (FieldType::String, VariableValue::String(_))
+        | (FieldType::Number, VariableValue::Number(_))
+        | (FieldType::Bool, VariableValue::Bool(_))
+        | (FieldType::Date, VariableValue::Date(_)) => true,
+        (FieldType::Array, VariableValue::Array(array)) => array
+            .iter()
+            .all(|value| matches!(value, VariableValue::String(_))),
+        _ => false,
+    };
+    if !matches {
+        return vec![format!("Field '{}' must be of type '{}'.", key, field.kind)];
+    }
+
+    if field.values.is_empty() {
+        return Vec::new();
+    }
+
+    let element_kind = match field.kind {
+        FieldType::Array => FieldType::String,
+        kind => kind,
+    };
+    let allowed = field
+        .values
+        .iter()
+        .filter_map(|value| convert(value, element_kind).ok())
+        .collect::<Vec<_>>();
+    let values = match value {
+        VariableValue::Array(array) => array.iter().collect(),
+        value => vec![value],
+    };
+
+    values
+        .into_iter()
+        .filter(|value| !allowed.contains(value))
+        .map(|value| {
+            format!(
+                "Value {} of field '{}' is not one of the allowed values: {}.",
+                display(value),
+                key,
+                allowed.iter().map(display).collect::<Vec<_>>().join(", ")
+            )
+        })
+        .collect()
+}
+
+fn convert(value: &FieldValue, kind: FieldType) -> anyhow::Result<VariableValue> {
+    Ok(match (kind, value) {
+        (FieldType::String, FieldValue::String(str)) => VariableValue::String(str.clone()),
+        (FieldType::Number, FieldValue::Number(number)) => VariableValue::Number(*number),
+        (FieldType::Bool, FieldValue::Bool(bool)) => VariableValue::Bool(*bool),
+        (FieldType::Date, value) => VariableValue::Date(value.to_date()?),
+        (FieldType::Array, FieldValue::Array(array)) => VariableValue::Array(
+            array
+                .iter()
+                .map(|value| convert(value, FieldType::String))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        _ => anyhow::bail!("expected value of type '{}'", kind),
+    })
+}
+
+fn display(value: &VariableValue) -> String {
+    match value {
+        VariableValue::String(str) => format!("\"{}\"", str),
+        VariableValue::Bool(bool) => bool.to_string(),
+        VariableValue::Number(number) => number.to_string(),
+        VariableValue::Date(date) => date.to_rfc3339(),
+        VariableValue::Array(array) => {
+            format!(
+                "[{}]",
+                array.iter().map(display).collect::<Vec<_>>().join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn field(kind: FieldType, required: bool, values: Vec<FieldValue>) -> FieldConfig {
+        FieldConfig {
+            kind,
+            required,
+            default: None,
+            values,
+        }
+    }
+
+    fn string(value: &str) -> FieldValue {
+        FieldValue::String(value.to_owned())
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("post.md");
+        fs::write(
+            &path,
+            "---\nlevel: 3\nstatus: \"done\"\ntopics: [\"a\", \"x\", \"y\"]\n---\nText",
+        )
+        .unwrap();
+
+        let fields = BTreeMap::from([
+            ("summary".to_owned(), field(FieldType::String, true, vec![])),
+            ("level".to_owned(), field(FieldType::String, false, vec![])),
+            (
+                "status".to_owned(),
+                field(FieldType::String, false, vec![string("draft")]),
+            ),
+            (
+                "topics".to_owned(),
+                field(FieldType::Array, false, vec![string("a"), string("b")]),
+            ),
+        ]);
+        let mut md_variables = HashMap::from([
+            ("level".to_owned(), VariableValue::Number(3.0)),
+            (
+                "status".to_owned(),
+                VariableValue::String("done".to_owned()),
+            ),
+            (
+                "topics".to_owned(),
+                VariableValue::Array(
+                    ["a", "x", "y"]
+                        .map(|topic| VariableValue::String(topic.to_owned()))
+                        .to_vec(),
+                ),
+            ),
+        ]);
+
+        let diagnostics = apply_fields(&path, &fields, &mut md_variables);
+        let located = diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), d.line, d.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            located,
+            [
+                ("Field 'level' must be of type 'string'.", Some(2), Some(1)),
+                (
+                    "Value \"done\" of field 'status' is not one of the allowed values: \"draft\".",
+                    Some(3),
+                    Some(1)
+                ),
+                (
+                    "Missing required front matter field 'summary'.",
+                    Some(1),
+                    Some(1)
+                ),
+                (
+                    "Value \"x\" of field 'topics' is not one of the allowed values: \"a\", \"b\".",
+                    Some(4),
+                    Some(1)
+                ),
+                (
+                    "Value \"y\" of field 'topics' is not one of the allowed values: \"a\", \"b\".",
+                    Some(4),
+                    Some(1)
+                ),
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.file.as_deref() == Some(&*path)));
+    }
+
+    #[test]
+    fn fills_in_defaults() {
+        let mut with_default = field(FieldType::Bool, false, vec![]);
+        with_default.default = Some(FieldValue::Bool(true));
+        let fields = BTreeMap::from([("comments".to_owned(), with_default)]);
+
+        let mut md_variables = HashMap::new();
+        let diagnostics = apply_fields(Path::new("post.md"), &fields, &mut md_variables);
+        assert!(diagnostics.is_empty());
+        assert_eq!(md_variables["comments"], VariableValue::Bool(true));
+
+        let mut md_variables = HashMap::from([("comments".to_owned(), VariableValue::Bool(false))]);
+        apply_fields(Path::new("post.md"), &fields, &mut md_variables);
+        assert_eq!(md_variables["comments"], VariableValue::Bool(false));
+    }
+
+    #[test]
+    fn validates_types_and_values() {
+        let number = field(FieldType::Number, false, vec![FieldValue::Number(1.0)]);
+        assert!(validate("n", &VariableValue::Number(1.0), &number).is_empty());
+        assert_eq!(validate("n", &VariableValue::Number(2.0), &number).len(), 1);
+        assert_eq!(
+            validate("n", &VariableValue::String("1".to_owned()), &number),
+            ["Field 'n' must be of type 'number'."]
+        );
+
+        let array = field(FieldType::Array, false, vec![]);
+        let numbers = VariableValue::Array(vec![VariableValue::Number(1.0)]);
+        assert_eq!(
+            validate("a", &numbers, &array),
+            ["Field 'a' must be of type 'array'."]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use pulldown_cmark::{Event, Parser, TagEnd};
+use vsm_search::SearchIndex;
+
+use crate::{output, routes, Context};
+
+use super::{markdown, preliminary_analysis::PreliminaryAnalysisOutput};
+
+pub fn extract_text(context: &Context, markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(markdown, markdown::get_parser_options(context)) {
+        match event {
+            Event::Text(str) | Event::Code(str) => text.push_str(&str),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item) => text.push(' '),
+            _ => (),
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub async fn create_search_index(
+    context: &Arc<Context>,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+) -> anyhow::Result<()> {
+    let mut index = SearchIndex::new();
+    for content in outputs
+        .iter()
+        .filter_map(|v| v.content.as_ref())
+        .filter(|v| !v.draft && !routes::is_error_page(&v.link))
+    {
+        index.add(
+            format!("/{}", content.link),
+            content.title.clone(),
+            content.description.clone(),
+            content.text.clone(),
+        );
+    }
+
+    let path = context.output.join("search.bin");
+    let count = index.len();
+    let data = tokio::task::spawn_blocking(move || index.to_bytes()).await??;
+    output::write(context, &path, &data, None).await?;
+
+    tracing::trace!("Created search index with {} documents.", count);
+    Ok(())
+}
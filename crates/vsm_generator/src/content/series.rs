@@ -0,0 +1,231 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use vsm_config::{SortBy, SortOrder, DEFAULT_COLLECTION};
+
+use crate::{
+    diagnostic::{self, Diagnostic},
+    Context,
+};
+
+use super::{
+    collections, escape_html, preliminary_analysis::PreliminaryAnalysisOutput, ContentResult,
+};
+
+/// Navigation between posts, exposed as `{{series_nav}}`, `{{prev_post}}`, `{{next_post}}` and
+/// `{{md_related_posts}}`.
+#[derive(Debug, Default)]
+pub struct Navigation {
+    pub series_nav: String,
+    pub prev_post: String,
+    pub next_post: String,
+    pub related_posts: String,
+}
+
+/// Creates navigation keyed by page links. Parts of a series link to each other in `series_order`,
+/// other posts link to chronologically adjacent posts.
+pub fn create_navigation(
+    context: &Context,
+    outputs: &[Arc<PreliminaryAnalysisOutput>],
+    result: &mut ContentResult,
+) -> HashMap<String, Navigation> {
+    let mut config = context
+        .config
+        .collections
+        .get(DEFAULT_COLLECTION)
+        .cloned()
+        .unwrap_or_default();
+    config.sort_by = SortBy::Date;
+    config.order = SortOrder::Asc;
+    config.limit = None;
+    let posts = collections::collect_pages(context, DEFAULT_COLLECTION, &config, outputs);
+
+    let mut navigation = HashMap::new();
+    let mut series = BTreeMap::<&str, Vec<&Arc<PreliminaryAnalysisOutput>>>::new();
+    for (index, output) in posts.iter().enumerate() {
+        let content = output.content.as_ref().unwrap();
+        if let Some(name) = &content.series {
+            series.entry(name).or_default().push(output);
+            continue;
+        }
+
+        navigation.insert(
+            output.link.clone(),
+            Navigation {
+                series_nav: String::new(),
+                prev_post: post_link("prev-post", index.checked_sub(1).map(|i| &posts[i])),
+                next_post: post_link("next-post", posts.get(index + 1)),
+                ..Default::default()
+            },
+        );
+    }
+
+    for (name, mut parts) in series {
+        sort_parts(&mut parts);
+
+        for pair in parts.windows(2) {
+            let order = pair[1].content.as_ref().unwrap().series_order;
+            if order.is_some() && order == pair[0].content.as_ref().unwrap().series_order {
+                let mut diagnostic = Diagnostic::warning(
+                    diagnostic::FRONT_MATTER,
+                    format!(
+                        "Part '/{}' of series '{}' has the same 'series_order' as '/{}'.",
+                        pair[1].link, name, pair[0].link
+                    ),
+                )
+                .with_needle("series_order");
+                diagnostic.locate([pair[1].path.as_path()]);
+                result.push(diagnostic.or_file(&pair[1].path));
+            }
+        }
+
+        for (index, output) in parts.iter().enumerate() {
+            navigation.insert(
+                output.link.clone(),
+                Navigation {
+                    series_nav: series_nav(name, &parts, index),
+                    prev_post: post_link("prev-post", index.checked_sub(1).map(|i| parts[i])),
+                    next_post: post_link("next-post", parts.get(index + 1).copied()),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    navigation
+}
+
+/// Sorts chronologically ordered parts by `series_order`, parts without it go last.
+fn sort_parts(parts: &mut [&Arc<PreliminaryAnalysisOutput>]) {
+    // Stable sort keeps chronological order of parts without `series_order`.
+    parts.sort_by(|a, b| {
+        let a = a.content.as_ref().unwrap().series_order;
+        let b = b.content.as_ref().unwrap().series_order;
+        a.unwrap_or(f64::MAX).total_cmp(&b.unwrap_or(f64::MAX))
+    });
+}
+
+fn series_nav(name: &str, parts: &[&Arc<PreliminaryAnalysisOutput>], current: usize) -> String {
+    let mut result = format!(
+        r#"<nav class="series"><p>Part {} of <strong>{}</strong></p><ol>"#,
+        current + 1,
+        escape_html(name)
+    );
+
+    for (index, part) in parts.iter().enumerate() {
+        let title = escape_html(&part.content.as_ref().unwrap().title);
+        match index == current {
+            true => result.push_str(&format!(
+                r#"<li class="series-current"><span>{}</span></li>"#,
+                title
+            )),
+            false => result.push_str(&format!(
+                r#"<li><a href="/{}">{}</a></li>"#,
+                escape_html(&part.link),
+                title
+            )),
+        }
+    }
+
+    result.push_str("</ol></nav>");
+    result
+}
+
+fn post_link(class: &str, output: Option<&Arc<PreliminaryAnalysisOutput>>) -> String {
+    let Some(output) = output else {
+        return String::new();
+    };
+
+    format!(
+        r#"<a class="{}" href="/{}">{}</a>"#,
+        class,
+        escape_html(&output.link),
+        escape_html(&output.content.as_ref().unwrap().title)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::{content_variables::ContentVariables, markdown::MarkdownContent};
+
+    fn part(link: &str, series_order: Option<f64>) -> Arc<PreliminaryAnalysisOutput> {
+        Arc::new(PreliminaryAnalysisOutput {
+            path: PathBuf::from(format!("{}.md", link)),
+            link: link.to_owned(),
+            template_path: PathBuf::new(),
+            variables: ContentVariables::new(),
+            content: Some(MarkdownContent {
+                link: link.to_owned(),
+                title: link.to_uppercase(),
+                series: Some("Series".to_owned()),
+                series_order,
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn links(parts: &[&Arc<PreliminaryAnalysisOutput>]) -> Vec<String> {
+        parts.iter().map(|part| part.link.clone()).collect()
+    }
+
+    #[test]
+    fn sorts_by_series_order() {
+        let parts = [
+            part("a", Some(3.0)),
+            part("b", Some(1.0)),
+            part("c", Some(2.5)),
+        ];
+        let mut parts = parts.iter().collect::<Vec<_>>();
+        sort_parts(&mut parts);
+        assert_eq!(links(&parts), ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn keeps_chronological_order_without_series_order() {
+        let parts = [
+            part("a", None),
+            part("b", Some(2.0)),
+            part("c", None),
+            part("d", Some(1.0)),
+        ];
+        let mut parts = parts.iter().collect::<Vec<_>>();
+        sort_parts(&mut parts);
+        assert_eq!(links(&parts), ["d", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn marks_current_part() {
+        let parts = [part("a", None), part("b", None)];
+        let parts = parts.iter().collect::<Vec<_>>();
+        let nav = series_nav("Series", &parts, 1);
+        assert!(nav.contains("<p>Part 2 of <strong>Series</strong></p>"));
+        assert!(nav.contains(r#"<li><a href="/a">A</a></li>"#));
+        assert!(nav.contains(r#"<li class="series-current"><span>B</span></li>"#));
+    }
+
+    #[test]
+    fn escapes_names_and_titles() {
+        let mut first = part("a", None);
+        Arc::get_mut(&mut first)
+            .unwrap()
+            .content
+            .as_mut()
+            .unwrap()
+            .title = "First <post> & more".to_owned();
+        let parts = [first, part("b", None)];
+        let parts = parts.iter().collect::<Vec<_>>();
+
+        let nav = series_nav("Rust & <C>", &parts, 1);
+        assert!(nav.contains("<strong>Rust &amp; &lt;C&gt;</strong>"));
+        assert!(nav.contains(r#"<a href="/a">First &lt;post&gt; &amp; more</a>"#));
+        assert_eq!(
+            post_link("prev-post", Some(parts[0])),
+            r#"<a class="prev-post" href="/a">First &lt;post&gt; &amp; more</a>"#
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{fontdb, Options, Tree},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{output, Context, Mode};
+
+use super::{escape_html, markdown::MarkdownContent};
+
+/// Used when the project does not provide fonts in the social directory.
+const BUNDLED_FONT: &[u8] = include_bytes!("../../assets/DejaVuSans.ttf");
+const TITLE_LINE_LENGTH: usize = 32;
+const TITLE_LINES: usize = 3;
+
+#[derive(Debug)]
+pub struct SocialCardTemplate {
+    data: String,
+    hash: [u8; 32],
+    directory: PathBuf,
+    fontdb: Arc<fontdb::Database>,
+}
+
+impl SocialCardTemplate {
+    pub fn load(project: &Path) -> anyhow::Result<Option<Self>> {
+        let directory = project.join("social");
+        let template = directory.join("card.svg");
+        if !template.exists() {
+            tracing::trace!("Social card template not found, skipping social cards.");
+            return Ok(None);
+        }
+
+        let data = fs::read_to_string(&template)?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(data.as_bytes());
+
+        let mut fontdb = fontdb::Database::new();
+        let mut fonts = fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "ttf" || ext == "otf")
+            })
+            .collect::<Vec<_>>();
+        fonts.sort();
+
+        for font in fonts {
+            let font_data = fs::read(&font)?;
+            hasher.update(&font_data);
+            fontdb.load_font_data(font_data);
+
+            tracing::trace!("Loaded social card font: '{}'.", font.display());
+        }
+
+        if fontdb.is_empty() {
+            tracing::trace!(
+                "No fonts found in '{}', using the bundled font for social cards.",
+                directory.display()
+            );
+            fontdb.load_font_data(BUNDLED_FONT.to_vec());
+        }
+
+        Ok(Some(Self {
+            data,
+            hash: hasher.finalize().into(),
+            directory,
+            fontdb: Arc::new(fontdb),
+        }))
+    }
+
+    fn fill(&self, context: &Context, content: &MarkdownContent) -> String {
+        let mut data = self.data.clone();
+
+        let mut variables = vec![
+            ("title".to_owned(), content.title.clone()),
+            ("description".to_owned(), content.description.clone()),
+            (
+                "date".to_owned(),
+                content.date.format("%e %B %Y").to_string(),
+            ),
+            (
+                "tags".to_owned(),
+                content
+                    .tags
+                    .iter()
+                    .map(|tag| format!("#{tag}"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            (
+                "site".to_owned(),
+                context
+                    .get_absolute_url("")
+                    .trim_start_matches("https://")
+                    .trim_start_matches("http://")
+                    .trim_end_matches('/')
+                    .to_owned(),
+            ),
+        ];
+
+        let lines = wrap_title(&content.title);
+        for i in 0..TITLE_LINES {
+            variables.push((
+                format!("title_line_{}", i + 1),
+                lines.get(i).cloned().unwrap_or_default(),
+            ));
+        }
+
+        for (key, value) in variables {
+            data = data.replace(&format!("{{{{{key}}}}}"), &escape_html(&value));
+        }
+
+        data
+    }
+
+    fn render(&self, data: &str) -> anyhow::Result<Vec<u8>> {
+        let mut options = Options {
+            resources_dir: Some(self.directory.clone()),
+            fontdb: self.fontdb.clone(),
+            ..Options::default()
+        };
+        if let Some(face) = self.fontdb.faces().next() {
+            if let Some((family, _)) = face.families.first() {
+                options.font_family = family.clone();
+            }
+        }
+
+        let tree = Tree::from_str(data, &options)?;
+        let size = tree.size().to_int_size();
+        let Some(mut pixmap) = Pixmap::new(size.width(), size.height()) else {
+            anyhow::bail!("Social card template has invalid size.");
+        };
+
+        resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+        Ok(pixmap.encode_png()?)
+    }
+}
+
+pub async fn create(
+    context: &Arc<Context>,
+    path: &Path,
+    content: &MarkdownContent,
+) -> anyhow::Result<Option<String>> {
+    let Some(template) = &context.social_card else {
+        return Ok(None);
+    };
+    if content.draft {
+        return Ok(None);
+    }
+
+    // Keeps the directory structure of links, so they can not collide.
+    let name = match content.link.is_empty() {
+        true => "index".to_owned(),
+        false => content.link.clone(),
+    };
+    let link = format!("/static/social/{name}.png");
+    let output_path = context
+        .output
+        .join("static")
+        .join("social")
+        .join(format!("{name}.png"));
+
+    if context.mode == Mode::Check {
+        return Ok(Some(link));
+    }
+
+    let data = template.fill(context, content);
+
+    let mut hasher = Sha256::new();
+    hasher.update(template.hash);
+    hasher.update(data.as_bytes());
+    let hash: [u8; 32] = hasher.finalize().into();
+
+    if output_path.exists() && context.cache.lock().unwrap().social_card_hash(&name) == Some(&hash)
+    {
+        let png = tokio::fs::read(&output_path).await?;
+        output::record(context, &output_path, &png, Some(path));
+
+        tracing::trace!("Social card '{}' is up to date.", name);
+        return Ok(Some(link));
+    }
+
+    let context_clone = context.clone();
+    let png = tokio::task::spawn_blocking(move || {
+        context_clone
+            .social_card
+            .as_ref()
+            .expect("Social card template disappeared.")
+            .render(&data)
+    })
+    .await??;
+
+    output::write(context, &output_path, &png, Some(path)).await?;
+
+    context
+        .cache
+        .lock()
+        .unwrap()
+        .set_social_card_hash(name, hash);
+
+    tracing::trace!("Rendered social card '{}'.", output_path.display());
+    Ok(Some(link))
+}
+
+fn wrap_title(title: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in title.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + word.chars().count() >= TITLE_LINE_LENGTH {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > TITLE_LINES {
+        lines.truncate(TITLE_LINES);
+        if let Some(last) = lines.last_mut() {
+            last.push('…');
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,75 @@
+use serde_json::{json, Value};
+
+use crate::Context;
+
+use super::markdown::MarkdownContent;
+
+pub fn create_md_json_ld(context: &Context, content: &MarkdownContent) -> anyhow::Result<String> {
+    let url = context.get_absolute_url(&content.link);
+
+    let mut posting = json!({
+        "@context": "https://schema.org",
+        "@type": "BlogPosting",
+        "headline": content.title,
+        "description": content.description,
+        "datePublished": content.date.to_rfc3339(),
+        "dateModified": content.updated.to_rfc3339(),
+        "keywords": content.tags.join(", "),
+        "wordCount": content.word_count,
+        "url": url,
+        "mainEntityOfPage": {
+            "@type": "WebPage",
+            "@id": url,
+        },
+        "author": context.authors.json_ld(context, &content.authors),
+    });
+    if let Some(image) = &content.image {
+        posting["image"] = Value::String(context.get_absolute_url(image));
+    }
+
+    let breadcrumbs = json!({
+        "@context": "https://schema.org",
+        "@type": "BreadcrumbList",
+        "itemListElement": create_breadcrumbs(context, content),
+    });
+
+    Ok(format!(
+        r#"<script type="application/ld+json">{}</script><script type="application/ld+json">{}</script>"#,
+        to_script_json(&posting)?,
+        to_script_json(&breadcrumbs)?
+    ))
+}
+
+fn create_breadcrumbs(context: &Context, content: &MarkdownContent) -> Vec<Value> {
+    let mut items = vec![json!({
+        "@type": "ListItem",
+        "position": 1,
+        "name": "Home",
+        "item": context.get_absolute_url(""),
+    })];
+
+    let segments = content
+        .link
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let is_last = i + 1 == segments.len();
+        items.push(json!({
+            "@type": "ListItem",
+            "position": i + 2,
+            "name": match is_last {
+                true => content.title.as_str(),
+                false => segment,
+            },
+            "item": context.get_absolute_url(&segments[..=i].join("/")),
+        }));
+    }
+
+    items
+}
+
+fn to_script_json(value: &Value) -> anyhow::Result<String> {
+    Ok(serde_json::to_string(value)?.replace("</", "<\\/"))
+}
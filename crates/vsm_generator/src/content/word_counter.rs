@@ -1,19 +1,250 @@
-use super::{content_variables::ContentVariables, markdown::MarkdownContent};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::Context;
+
+use super::{content_variables::ContentVariables, markdown, markdown::MarkdownContent};
+
+/// Used for languages missing in `reading.words_per_minute`.
+const DEFAULT_WORDS_PER_MINUTE: f64 = 240.0;
+const CODE_WORDS_PER_MINUTE: f64 = 100.0;
+/// Reading speed drops by this fraction per point of difficulty.
+const DIFFICULTY_SLOWDOWN: f64 = 0.0625;
+const MAX_DIFFICULTY: f64 = 10.0;
+/// The first image takes this long to look at, every next one a second less down to the minimum.
+const IMAGE_SECONDS: f64 = 12.0;
+const MIN_IMAGE_SECONDS: f64 = 3.0;
+/// Share of words with Polish diacritics above which the page is read as Polish.
+const POLISH_THRESHOLD: f64 = 0.05;
+/// Words longer than this are counted as long by the readability index.
+const LONG_WORD_LENGTH: usize = 6;
+/// Readability index of very easy text, which gets difficulty 0.
+const EASY_READABILITY: f64 = 20.0;
+/// Readability index points per point of difficulty, so text with an index of 70 and above gets
+/// [`MAX_DIFFICULTY`].
+const READABILITY_PER_DIFFICULTY: f64 = 5.0;
+
+#[derive(Debug, Default)]
+struct Statistics {
+    /// Prose, including inline code, link text and headings
+    words: Vec<String>,
+    code_words: usize,
+    images: usize,
+    sentences: usize,
+}
 
 pub fn compute_read_time(
+    context: &Context,
     file_content: &str,
+    manual_difficulty: bool,
     content: &mut MarkdownContent,
     variables: &mut ContentVariables,
 ) {
-    let word_count = words_count::count(file_content).words as u64;
+    let statistics = collect_statistics(markdown::get_parser_options(context), file_content);
+    let word_count = statistics.words.len() as u64;
+    content.word_count = word_count;
     variables.insert("md_word_count".to_owned(), word_count.to_string());
 
-    // TODO: Use beter algorithm to calculate read time
-    let wpm = 240.0 - (content.difficulty * 15.0);
-    let read_time = (word_count as f64 / wpm) * 60.0;
+    let readability = readability(&statistics);
+    variables.insert("md_readability".to_owned(), readability.round().to_string());
+    if context.config.reading.auto_difficulty && !manual_difficulty {
+        content.difficulty = difficulty(readability);
+    }
+    variables.insert("md_difficulty".to_owned(), content.difficulty.to_string());
+
+    let language = match &content.lang {
+        Some(lang) => lang.as_str(),
+        None if is_polish(&statistics.words) => "pl",
+        None => context.config.reading.language.as_str(),
+    };
+    let words_per_minute = context
+        .config
+        .reading
+        .words_per_minute
+        .get(language)
+        .copied()
+        .unwrap_or(DEFAULT_WORDS_PER_MINUTE);
+    let read_time = read_time(&statistics, words_per_minute, content.difficulty);
+
+    variables.insert("md_read_time".to_owned(), read_time.round().to_string());
+}
+
+/// Minutes of reading prose at the speed slowed down by difficulty, code and images.
+fn read_time(statistics: &Statistics, words_per_minute: f64, difficulty: f64) -> f64 {
+    let words_per_minute =
+        words_per_minute * (1.0 - difficulty.clamp(0.0, MAX_DIFFICULTY) * DIFFICULTY_SLOWDOWN);
+
+    let image_seconds = (0..statistics.images)
+        .map(|index| (IMAGE_SECONDS - index as f64).max(MIN_IMAGE_SECONDS))
+        .sum::<f64>();
+    statistics.words.len() as f64 / words_per_minute
+        + statistics.code_words as f64 / CODE_WORDS_PER_MINUTE
+        + image_seconds / 60.0
+}
+
+fn collect_statistics(options: Options, file_content: &str) -> Statistics {
+    let mut statistics = Statistics::default();
+    let mut code_block = false;
+    let mut image = false;
+    let mut link = None;
+
+    for event in Parser::new_ext(file_content, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => code_block = true,
+            Event::End(TagEnd::CodeBlock) => code_block = false,
+            Event::Start(Tag::Image { .. }) => {
+                image = true;
+                statistics.images += 1;
+            }
+            Event::End(TagEnd::Image) => image = false,
+            Event::Start(Tag::Link { dest_url, .. }) => link = Some(dest_url),
+            Event::End(TagEnd::Link) => link = None,
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item) => {
+                statistics.sentences += 1
+            }
+            // Alt text is skipped, the image itself is accounted for by time.
+            Event::Text(_) if image => (),
+            Event::Text(text) if code_block => {
+                statistics.code_words += text.split_whitespace().count()
+            }
+            // Bare URLs of autolinks count as a single word.
+            Event::Text(text) if link.as_deref() == Some(&*text) => {
+                statistics.words.push(text.to_string())
+            }
+            Event::Text(text) => {
+                statistics.sentences += text
+                    .split(['.', '!', '?'])
+                    .skip(1)
+                    .filter(|rest| rest.starts_with(char::is_whitespace))
+                    .count();
+                statistics.words.extend(words(&text));
+            }
+            Event::Code(code) => statistics.words.push(code.to_string()),
+            _ => (),
+        }
+    }
+
+    statistics
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(str::to_owned)
+}
+
+/// LIX readability index, works for both Polish and English. Below 30 is very easy and above 60
+/// very hard text.
+fn readability(statistics: &Statistics) -> f64 {
+    if statistics.words.is_empty() {
+        return 0.0;
+    }
+
+    let words = statistics.words.len() as f64;
+    let long_words = statistics
+        .words
+        .iter()
+        .filter(|word| word.chars().count() > LONG_WORD_LENGTH)
+        .count() as f64;
+    words / statistics.sentences.max(1) as f64 + 100.0 * long_words / words
+}
+
+/// Maps the readability index linearly onto difficulty, rounded to one decimal place.
+fn difficulty(readability: f64) -> f64 {
+    let difficulty = (readability - EASY_READABILITY) / READABILITY_PER_DIFFICULTY;
+    (difficulty.clamp(0.0, MAX_DIFFICULTY) * 10.0).round() / 10.0
+}
+
+fn is_polish(words: &[String]) -> bool {
+    if words.is_empty() {
+        return false;
+    }
+
+    let polish = words
+        .iter()
+        .filter(|word| word.chars().any(|c| "ąćęłńóśźżĄĆĘŁŃÓŚŹŻ".contains(c)))
+        .count();
+    polish as f64 / words.len() as f64 > POLISH_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(words: usize, sentences: usize) -> Statistics {
+        Statistics {
+            words: vec!["word".to_owned(); words],
+            sentences,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn collects_statistics() {
+        let markdown = concat!(
+            "# Heading\n\n",
+            "First sentence here. Second `code` one! See <https://example.org/a.b>.\n\n",
+            "![alt text](a.png) ![](b.png)\n\n",
+            "```rust\nfn main() {}\n```\n"
+        );
+        let statistics = collect_statistics(Options::empty(), markdown);
+        assert_eq!(
+            statistics.words,
+            [
+                "Heading",
+                "First",
+                "sentence",
+                "here",
+                "Second",
+                "code",
+                "one",
+                "See",
+                "https://example.org/a.b"
+            ]
+        );
+        assert_eq!(statistics.code_words, 3);
+        assert_eq!(statistics.images, 2);
+        // Heading, three sentences and the paragraph with images.
+        assert_eq!(statistics.sentences, 5);
+    }
+
+    #[test]
+    fn computes_lix_readability() {
+        assert_eq!(readability(&Statistics::default()), 0.0);
+        // 20 words per sentence, no long words.
+        assert_eq!(readability(&statistics(40, 2)), 20.0);
+
+        let mut statistics = statistics(10, 1);
+        statistics.words[..4].fill("readability".to_owned());
+        assert_eq!(readability(&statistics), 50.0);
+    }
+
+    #[test]
+    fn maps_readability_to_difficulty() {
+        assert_eq!(difficulty(10.0), 0.0);
+        assert_eq!(difficulty(EASY_READABILITY), 0.0);
+        assert_eq!(difficulty(42.34), 4.5);
+        assert_eq!(difficulty(70.0), MAX_DIFFICULTY);
+        assert_eq!(difficulty(120.0), MAX_DIFFICULTY);
+    }
+
+    #[test]
+    fn computes_read_time() {
+        assert_eq!(read_time(&statistics(480, 1), 240.0, 0.0), 2.0);
+        // Difficulty 8 halves the reading speed.
+        assert_eq!(read_time(&statistics(480, 1), 240.0, 8.0), 4.0);
+
+        let mut statistics = statistics(0, 0);
+        statistics.code_words = 100;
+        statistics.images = 12;
+        // 12 + 11 + ... + 3 seconds for the first ten images and 3 seconds for the other two.
+        assert_eq!(read_time(&statistics, 240.0, 0.0), 1.0 + 81.0 / 60.0);
+    }
 
-    variables.insert(
-        "md_read_time".to_owned(),
-        (read_time / 60.0).round().to_string(),
-    );
+    #[test]
+    fn detects_polish() {
+        let words = |text: &str| words(text).collect::<Vec<_>>();
+        assert!(is_polish(&words("To jest krótki wpis o języku Rust")));
+        assert!(!is_polish(&words("This is a short post about Rust")));
+        assert!(!is_polish(&[]));
+    }
 }
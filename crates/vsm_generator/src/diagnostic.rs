@@ -0,0 +1,329 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+pub const GENERIC: &str = "generic";
+pub const IO: &str = "io";
+pub const MISSING_TEMPLATE: &str = "missing-template";
+pub const TEMPLATE_SYNTAX: &str = "template-syntax";
+pub const UNTERMINATED_VARIABLE: &str = "unterminated-variable";
+pub const UNDEFINED_VARIABLE: &str = "undefined-variable";
+pub const FRONT_MATTER: &str = "front-matter";
+pub const BROKEN_LINK: &str = "broken-link";
+pub const BROKEN_ANCHOR: &str = "broken-anchor";
+pub const INVALID_REDIRECT: &str = "invalid-redirect";
+pub const REDIRECT_COLLISION: &str = "redirect-collision";
+pub const INVALID_PERMALINK: &str = "invalid-permalink";
+pub const DUPLICATE_PERMALINK: &str = "duplicate-permalink";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    #[serde(skip)]
+    length: usize,
+    /// Text searched for in the source files to find the location of the diagnostic
+    #[serde(skip)]
+    needle: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            file: None,
+            line: None,
+            column: None,
+            length: 1,
+            needle: None,
+        }
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn with_file(mut self, file: &Path) -> Self {
+        self.file = Some(file.to_path_buf());
+        self
+    }
+
+    /// Sets the file only if the diagnostic does not have one yet.
+    pub fn or_file(mut self, file: &Path) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file.to_path_buf());
+        }
+        self
+    }
+
+    pub fn with_span(mut self, line: usize, column: usize, length: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self.length = length.max(1);
+        self
+    }
+
+    pub fn with_needle(mut self, needle: &str) -> Self {
+        self.length = needle.chars().count().max(1);
+        self.needle = Some(needle.to_owned());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub fn is_located(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Finds the first occurrence of the needle in the given files and points the diagnostic at it.
+    pub fn locate<'a>(&mut self, files: impl IntoIterator<Item = &'a Path>) {
+        let Some(needle) = &self.needle else {
+            return;
+        };
+
+        for file in files {
+            let Ok(source) = fs::read_to_string(file) else {
+                continue;
+            };
+            let Some(offset) = source.find(needle.as_str()) else {
+                continue;
+            };
+
+            let before = &source[..offset];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            self.file = Some(file.to_path_buf());
+            self.line = Some(before.matches('\n').count() + 1);
+            self.column = Some(source[line_start..offset].chars().count() + 1);
+            return;
+        }
+    }
+
+    /// Renders the diagnostic with an annotated snippet of the source file.
+    pub fn render(&self) -> String {
+        let mut result = format!("{}[{}]: {}", self.severity, self.code, self.message);
+
+        let Some(file) = &self.file else {
+            return result;
+        };
+        let Some(line) = self.line else {
+            result.push_str(&format!("\n --> {}", file.display()));
+            return result;
+        };
+
+        let location = match self.column {
+            Some(column) => format!("{}:{}:{}", file.display(), line, column),
+            None => format!("{}:{}", file.display(), line),
+        };
+
+        let source_line = fs::read_to_string(file)
+            .ok()
+            .and_then(|source| source.lines().nth(line - 1).map(|l| l.to_owned()));
+        let Some(source_line) = source_line else {
+            result.push_str(&format!("\n --> {}", location));
+            return result;
+        };
+
+        let gutter = " ".repeat(line.to_string().len());
+        result.push_str(&format!("\n{gutter}--> {location}"));
+        result.push_str(&format!("\n{gutter} |"));
+        result.push_str(&format!("\n{line} | {source_line}"));
+        if let Some(column) = self.column {
+            let length = self
+                .length
+                .min(source_line.chars().count().saturating_sub(column - 1))
+                .max(1);
+            result.push_str(&format!(
+                "\n{gutter} | {}{}",
+                " ".repeat(column - 1),
+                "^".repeat(length)
+            ));
+        }
+
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Unable to serialize diagnostic.")
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(column)) => {
+                write!(f, "{}:{}:{}: ", file.display(), line, column)?
+            }
+            (Some(file), Some(line), None) => write!(f, "{}:{}: ", file.display(), line)?,
+            (Some(file), None, _) => write!(f, "{}: ", file.display())?,
+            _ => (),
+        }
+
+        write!(f, "{} [{}]", self.message, self.code)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Several diagnostics returned as one error, e.g. all front matter violations of a page.
+#[derive(Debug)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, diagnostic) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// Converts the error into diagnostics, unpacking [`Diagnostics`].
+pub fn collect(error: anyhow::Error) -> Vec<Diagnostic> {
+    match error.downcast::<Diagnostics>() {
+        Ok(diagnostics) => diagnostics.0,
+        Err(error) => vec![error.into()],
+    }
+}
+
+impl From<anyhow::Error> for Diagnostic {
+    fn from(error: anyhow::Error) -> Self {
+        if error.is::<Diagnostic>() {
+            return error.downcast().expect("Checked by is.");
+        }
+
+        let code = match error.is::<std::io::Error>() {
+            true => IO,
+            false => GENERIC,
+        };
+        Diagnostic::error(code, format!("{:#}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_source(source: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.md");
+        fs::write(&path, source).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn locates_needle_in_later_file() {
+        let (_dir, path) = write_source("---\ntitle: \"Post\"\n---\n\nSee [docs](/missing).\n");
+        let mut diagnostic = Diagnostic::error(BROKEN_LINK, "Broken.").with_needle("/missing");
+
+        diagnostic.locate([Path::new("/nonexistent/file.md"), path.as_path()]);
+        assert_eq!(diagnostic.file.as_deref(), Some(path.as_path()));
+        assert_eq!(diagnostic.line, Some(5));
+        assert_eq!(diagnostic.column, Some(12));
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let (_dir, path) = write_source("# Über\n\nŽluťoučký kůň [odkaz](/chybí)\n");
+        let mut diagnostic = Diagnostic::error(BROKEN_LINK, "Broken.").with_needle("/chybí");
+
+        diagnostic.locate([path.as_path()]);
+        assert_eq!(diagnostic.line, Some(3));
+        assert_eq!(diagnostic.column, Some(23));
+    }
+
+    #[test]
+    fn keeps_location_when_needle_is_missing() {
+        let (_dir, path) = write_source("Nothing here.\n");
+        let mut diagnostic = Diagnostic::error(BROKEN_LINK, "Broken.").with_needle("/missing");
+
+        diagnostic.locate([path.as_path()]);
+        assert!(!diagnostic.is_located());
+        assert_eq!(diagnostic.line, None);
+    }
+
+    #[test]
+    fn renders_snippet() {
+        let (_dir, path) = write_source("# Über\n\nŽluťoučký kůň [odkaz](/chybí)\n");
+        let mut diagnostic =
+            Diagnostic::error(BROKEN_LINK, "Link does not exist.").with_needle("/chybí");
+        diagnostic.locate([path.as_path()]);
+
+        assert_eq!(
+            diagnostic.render(),
+            format!(
+                "error[broken-link]: Link does not exist.\n \
+                 --> {}:3:23\n  \
+                 |\n\
+                 3 | Žluťoučký kůň [odkaz](/chybí)\n  \
+                 |                       ^^^^^^",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn renders_without_location() {
+        let diagnostic = Diagnostic::warning(GENERIC, "Something happened.");
+        assert_eq!(diagnostic.render(), "warning[generic]: Something happened.");
+
+        let diagnostic = diagnostic.with_file(Path::new("/nonexistent/page.md"));
+        assert_eq!(
+            diagnostic.render(),
+            "warning[generic]: Something happened.\n --> /nonexistent/page.md"
+        );
+    }
+
+    #[test]
+    fn collects_multiple_diagnostics() {
+        let error = anyhow::Error::new(Diagnostics(vec![
+            Diagnostic::error(FRONT_MATTER, "First."),
+            Diagnostic::error(FRONT_MATTER, "Second."),
+        ]));
+        let messages = collect(error)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["First.", "Second."]);
+
+        let diagnostics = collect(anyhow::anyhow!("Failure."));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, GENERIC);
+    }
+}
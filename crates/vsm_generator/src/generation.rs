@@ -0,0 +1,152 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use chrono::Utc;
+use walkdir::WalkDir;
+
+use crate::Config;
+
+pub const GENERATIONS_DIRECTORY: &str = "generations";
+/// File in the output directory containing the id of the generation which is served.
+pub const CURRENT_FILE: &str = "current";
+/// File in the generations directory locked while generations are created or removed.
+const LOCK_FILE: &str = ".lock";
+
+fn generations_directory(config: &Config) -> PathBuf {
+    config.build.output.join(GENERATIONS_DIRECTORY)
+}
+
+pub fn path(config: &Config, id: &str) -> PathBuf {
+    generations_directory(config).join(id)
+}
+
+pub fn current_id(config: &Config) -> Option<String> {
+    let id = fs::read_to_string(config.build.output.join(CURRENT_FILE)).ok()?;
+    let id = id.trim().to_owned();
+    path(config, &id).is_dir().then_some(id)
+}
+
+/// Directory with the currently served website, falls back to the output directory itself for
+/// websites generated before generations were introduced.
+pub fn current(config: &Config) -> PathBuf {
+    match current_id(config) {
+        Some(id) => path(config, &id),
+        None => config.build.output.clone(),
+    }
+}
+
+/// Ids of all generations, from the oldest to the newest.
+pub fn list(config: &Config) -> anyhow::Result<Vec<String>> {
+    let directory = generations_directory(config);
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            ids.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+
+    ids.sort();
+    Ok(ids)
+}
+
+/// Waits for an exclusive lock of generations, held until the returned file is dropped. Prevents
+/// other processes building into the same output, e.g. `vsm` and `vsm_generator`, from removing
+/// the staging generation of this one.
+pub fn lock(config: &Config) -> anyhow::Result<fs::File> {
+    let directory = generations_directory(config);
+    fs::create_dir_all(&directory)?;
+
+    let path = directory.join(LOCK_FILE);
+    let file = fs::File::create(&path)
+        .with_context(|| format!("Unable to create lock file '{}'", path.display()))?;
+    file.lock()
+        .with_context(|| format!("Unable to lock '{}'", path.display()))?;
+    Ok(file)
+}
+
+/// Creates a new generation directory prefilled with links to files of the current generation, so
+/// unchanged files do not need to be generated again.
+pub fn create_staging(config: &Config) -> anyhow::Result<(String, PathBuf)> {
+    let id = Utc::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let staging = path(config, &id);
+
+    fs::create_dir_all(generations_directory(config))?;
+    fs::create_dir(&staging)
+        .with_context(|| format!("Unable to create generation '{}'", staging.display()))?;
+
+    if let Some(current) = current_id(config) {
+        link_tree(&path(config, &current), &staging)
+            .with_context(|| format!("Unable to copy generation '{}'", current))?;
+    }
+
+    tracing::trace!("Created staging generation '{}'.", id);
+    Ok((id, staging))
+}
+
+/// Atomically switches the served website to the given generation.
+pub fn activate(config: &Config, id: &str) -> anyhow::Result<()> {
+    if !path(config, id).is_dir() {
+        anyhow::bail!("Generation '{}' does not exist.", id);
+    }
+
+    let temporary = config.build.output.join(format!("{CURRENT_FILE}.tmp"));
+    fs::write(&temporary, id)?;
+    fs::rename(&temporary, config.build.output.join(CURRENT_FILE))?;
+
+    tracing::info!("Activated generation '{}'.", id);
+    Ok(())
+}
+
+/// Activates the newest generation older than the current one.
+pub fn rollback(config: &Config) -> anyhow::Result<String> {
+    let Some(current) = current_id(config) else {
+        anyhow::bail!("There is no active generation.");
+    };
+
+    let Some(previous) = list(config)?.into_iter().rfind(|id| *id < current) else {
+        anyhow::bail!("There is no generation older than '{}'.", current);
+    };
+
+    activate(config, &previous)?;
+    Ok(previous)
+}
+
+pub fn remove(config: &Config, id: &str) -> anyhow::Result<()> {
+    fs::remove_dir_all(path(config, id))?;
+    tracing::trace!("Removed generation '{}'.", id);
+    Ok(())
+}
+
+/// Removes all generations except the given ones.
+pub fn retain(config: &Config, keep: &[&str]) -> anyhow::Result<()> {
+    for id in list(config)? {
+        if !keep.contains(&id.as_str()) {
+            remove(config, &id)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn link_tree(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(from).min_depth(1) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from).expect("Walked from root."));
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if fs::hard_link(entry.path(), &target).is_err() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
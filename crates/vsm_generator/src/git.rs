@@ -0,0 +1,24 @@
+use std::{path::Path, process::Command};
+
+/// Runs git in the project directory, returns trimmed stdout or `None` if git is unavailable or
+/// the project is not a repository.
+pub fn run(project: &Path, args: &[&str]) -> Option<String> {
+    let output = match Command::new("git").args(args).current_dir(project).output() {
+        Ok(output) => output,
+        Err(error) => {
+            tracing::trace!("Unable to run git: {}.", error);
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        tracing::trace!(
+            "Git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
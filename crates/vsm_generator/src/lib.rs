@@ -0,0 +1,266 @@
+pub mod cache;
+pub mod content;
+pub mod diagnostic;
+pub mod generation;
+pub mod git;
+pub mod output;
+pub mod routes;
+pub mod static_files;
+pub mod template;
+pub mod template_repository;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context as _;
+use cache::Cache;
+use content::{
+    authors::Authors, build_metadata::BuildMetadata, collections::Collection, history::History,
+    series::Navigation, social_card::SocialCardTemplate,
+};
+use output::Manifest;
+use template_repository::TemplateRepository;
+
+pub use content::ContentResult;
+pub use diagnostic::{Diagnostic, Severity};
+pub use vsm_config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Writes the generated website to the output directory.
+    Build,
+    /// Validates the project without writing anything, additionally checking links and anchors.
+    Check,
+}
+
+#[derive(Debug)]
+pub struct Context {
+    mode: Mode,
+    templates: TemplateRepository,
+    social_card: Option<SocialCardTemplate>,
+    cache: Arc<Mutex<Cache>>,
+    config: Config,
+    authors: Authors,
+    metadata: BuildMetadata,
+    history: History,
+    /// Contents of data files keyed by their names
+    data: serde_json::Value,
+    collections: OnceLock<HashMap<String, Collection>>,
+    /// Keyed by page links
+    navigation: OnceLock<HashMap<String, Navigation>>,
+    manifest: Mutex<Manifest>,
+    /// Directory where the generated website is written, the staging generation while building
+    output: PathBuf,
+}
+
+impl Context {
+    pub fn get_file_link(&self, path: &Path) -> String {
+        let mut p = path
+            .strip_prefix(self.config.project_content())
+            .expect("Unable to strip prefix")
+            .to_owned();
+        p.set_extension("");
+
+        let str = p.to_str().expect("Unable to convert to str");
+        match str == "index" {
+            true => "",
+            false => str,
+        }
+        .replace('\\', "/")
+        .to_owned()
+    }
+
+    pub fn get_absolute_url(&self, link: &str) -> String {
+        if link.starts_with("http://") || link.starts_with("https://") {
+            return link.to_owned();
+        }
+
+        format!(
+            "{}/{}",
+            self.config.site.base_url.trim_end_matches('/'),
+            link.trim_start_matches('/')
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct BuildReport {
+    pub result: ContentResult,
+    pub duration: Duration,
+    /// Id of the generation activated by this build
+    pub generation: Option<String>,
+}
+
+impl BuildReport {
+    pub fn is_success(&self) -> bool {
+        self.result.errors().is_empty()
+    }
+}
+
+/// Keeps state which can be reused between builds, like the cache.
+#[derive(Debug)]
+pub struct Generator {
+    cache: Arc<Mutex<Cache>>,
+    build_lock: tokio::sync::Mutex<()>,
+}
+
+impl Generator {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let cache = Cache::load_or_new(config.build.output.join(".cache").join("cache.bin"))
+            .context("Failed to load cache")?;
+
+        Ok(Self {
+            cache: Arc::new(Mutex::new(cache)),
+            build_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub async fn generate(&self, config: &Config) -> anyhow::Result<BuildReport> {
+        self.run(config, Mode::Build).await
+    }
+
+    pub async fn check(&self, config: &Config) -> anyhow::Result<BuildReport> {
+        self.run(config, Mode::Check).await
+    }
+
+    /// Activates the previous generation, waiting for a running build to finish first.
+    pub async fn rollback(&self, config: &Config) -> anyhow::Result<String> {
+        let _guard = self.build_lock.lock().await;
+        let _generation_lock = lock_generations(config).await?;
+        generation::rollback(config)
+    }
+
+    async fn run(&self, config: &Config, mode: Mode) -> anyhow::Result<BuildReport> {
+        let _guard = self.build_lock.lock().await;
+        let start = Instant::now();
+
+        let templates =
+            TemplateRepository::load(&config.project).context("Failed to load templates")?;
+        let social_card = SocialCardTemplate::load(&config.project)
+            .context("Failed to load social card template")?;
+        let authors = Authors::load(config).context("Failed to load authors")?;
+        let data = content::data::load(config).context("Failed to load data files")?;
+
+        let (_generation_lock, staging) = match mode {
+            Mode::Build => (
+                Some(lock_generations(config).await?),
+                Some(generation::create_staging(config)?),
+            ),
+            Mode::Check => (None, None),
+        };
+
+        let context = Arc::new(Context {
+            mode,
+            templates,
+            social_card,
+            cache: self.cache.clone(),
+            config: config.clone(),
+            authors,
+            metadata: BuildMetadata::load(config),
+            history: History::load(config),
+            data,
+            collections: OnceLock::new(),
+            navigation: OnceLock::new(),
+            manifest: Mutex::new(Manifest::default()),
+            output: match &staging {
+                Some((_, path)) => path.clone(),
+                None => config.build.output.clone(),
+            },
+        });
+        let result = match mode {
+            Mode::Build => {
+                let (content_result, static_result) = tokio::join!(
+                    content::process_content(&context),
+                    static_files::process_static(&context)
+                );
+
+                let mut result = content_result.context("Failed to process content")?;
+                result.extend(static_result);
+                result
+            }
+            Mode::Check => content::process_content(&context)
+                .await
+                .context("Failed to process content")?,
+        };
+
+        if let Err(err) = self.cache.lock().unwrap().save() {
+            tracing::error!("Failed to save cache: {}", err);
+        }
+
+        let generation = match staging {
+            Some((id, _)) => finish_generation(&context, &result, id).await?,
+            None => None,
+        };
+
+        Ok(BuildReport {
+            result,
+            duration: start.elapsed(),
+            generation,
+        })
+    }
+}
+
+async fn lock_generations(config: &Config) -> anyhow::Result<std::fs::File> {
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || generation::lock(&config))
+        .await?
+        .context("Failed to lock generations")
+}
+
+/// Writes the manifest, removes stale files and activates the staging generation. Generations with
+/// errors are discarded, so the previous one stays served, unless `build.activate_with_errors` is
+/// set. Then stale files are kept, so pages which failed are served from the previous build.
+async fn finish_generation(
+    context: &Context,
+    result: &ContentResult,
+    id: String,
+) -> anyhow::Result<Option<String>> {
+    let config = &context.config;
+    let failed = !result.errors().is_empty();
+    if failed && !config.build.activate_with_errors {
+        tracing::warn!("Build finished with errors, keeping the current generation.");
+        generation::remove(config, &id)?;
+        return Ok(None);
+    }
+
+    let manifest = std::mem::take(&mut *context.manifest.lock().unwrap());
+    manifest
+        .save(&context.output)
+        .await
+        .context("Failed to save build manifest")?;
+
+    if failed {
+        tracing::warn!(
+            "Build finished with errors, activating it with previous versions of failed pages."
+        );
+    } else {
+        let output = context.output.clone();
+        let removed = tokio::task::spawn_blocking(move || output::prune(&output, &manifest))
+            .await?
+            .context("Failed to remove stale output files")?;
+        if removed > 0 {
+            tracing::info!("Removed {} stale output files.", removed);
+        }
+    }
+
+    let previous = generation::current_id(config);
+    generation::activate(config, &id)?;
+
+    let mut keep = vec![id.as_str()];
+    keep.extend(previous.as_deref());
+    generation::retain(config, &keep).context("Failed to remove old generations")?;
+
+    Ok(Some(id))
+}
+
+pub async fn generate(config: &Config) -> anyhow::Result<BuildReport> {
+    Generator::new(config)?.generate(config).await
+}
+
+pub async fn check(config: &Config) -> anyhow::Result<BuildReport> {
+    Generator::new(config)?.check(config).await
+}
@@ -1,59 +1,75 @@
-pub mod cache;
-pub mod content;
-pub mod static_files;
-pub mod template;
-pub mod template_repository;
+use std::path::PathBuf;
 
-use std::{
-    path::{Path, PathBuf},
-    sync::{Arc, OnceLock},
-};
+use clap::{Parser, Subcommand, ValueEnum};
+use vsm_generator::{BuildReport, Config};
 
-use clap::Parser;
-use template_repository::TemplateRepository;
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum MessageFormat {
+    /// Annotated source snippets
+    Human,
+    /// One JSON object per line on stdout
+    Json,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// Generate the website into the output directory (default)
+    Build,
+    /// Validate the project and check links without writing any output
+    Check,
+}
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Path to the project directory
-    #[arg(short, long, default_value = "../vixenka.com")]
-    project: String,
-    /// Path to the output directory
-    #[arg(short, long, default_value = "./output")]
-    output: String,
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "VSM_PROJECT",
+        default_value = "../vixenka.com"
+    )]
+    project: PathBuf,
+    /// Path to the output directory, overrides `build.output`
+    #[arg(short, long, global = true, env = "VSM_OUTPUT")]
+    output: Option<PathBuf>,
+    /// Base URL of the website, overrides `site.base_url`
+    #[arg(short, long, global = true, env = "VSM_BASE_URL")]
+    base_url: Option<String>,
+    /// Build profile from the project configuration
+    #[arg(long, global = true, env = "VSM_PROFILE")]
+    profile: Option<String>,
+    /// Format of reported diagnostics
+    #[arg(long, global = true, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+    /// Exit with a non-zero code when any warnings are reported
+    #[arg(long, global = true)]
+    deny_warnings: bool,
 }
 
 impl Args {
-    pub fn project_content(&self) -> PathBuf {
-        Path::new(&self.project).join("content")
-    }
-}
-
-#[derive(Debug)]
-pub struct Context {
-    templates: TemplateRepository,
-    args: Args,
-    md_post_list: OnceLock<String>,
-}
-
-impl Context {
-    pub fn get_file_link(&self, path: &Path) -> String {
-        let mut p = path
-            .strip_prefix(&self.args.project_content())
-            .expect("Unable to strip prefix")
-            .to_owned();
-        p.set_extension("");
-
-        let str = p.to_str().expect("Unable to convert to str");
-        match str == "index" {
-            true => "",
-            false => str,
+    fn load_config(&self) -> anyhow::Result<Config> {
+        let mut config = Config::load(&self.project, self.profile.as_deref())?;
+        if let Some(output) = &self.output {
+            config.build.output = output.clone();
         }
-        .replace('\\', "/")
-        .to_owned()
+        if let Some(base_url) = &self.base_url {
+            config.site.base_url = base_url.clone();
+        }
+
+        config.validate()?;
+        Ok(config)
     }
 }
 
+/// Exit code used when the build finished with errors, or warnings with `--deny-warnings`.
+const EXIT_BUILD_FAILED: i32 = 1;
+/// Exit code used when the build could not run at all.
+const EXIT_FATAL: i32 = 2;
+
 #[tokio::main]
 async fn main() {
     #[cfg(debug_assertions)]
@@ -61,7 +77,7 @@ async fn main() {
     #[cfg(not(debug_assertions))]
     let logger;
 
-    logger = tracing_subscriber::fmt();
+    logger = tracing_subscriber::fmt().with_writer(std::io::stderr);
     #[cfg(debug_assertions)]
     {
         logger = logger.with_max_level(tracing::Level::TRACE);
@@ -69,51 +85,49 @@ async fn main() {
     logger.init();
 
     let args = Args::parse();
-    /*let cache = Cache::load_or_new(
-        PathBuf::from(&args.project)
-            .join(".cache")
-            .join("cache.bin"),
-    )
-    .unwrap();*/
+    let config = match args.load_config() {
+        Ok(config) => config,
+        Err(err) => {
+            tracing::error!("Invalid configuration: {:#}", err);
+            std::process::exit(EXIT_FATAL);
+        }
+    };
 
-    let templates = match TemplateRepository::load(Path::new(&args.project)) {
-        Ok(templates) => templates,
+    let command = args.command.unwrap_or(Command::Build);
+    let report = match command {
+        Command::Build => vsm_generator::generate(&config).await,
+        Command::Check => vsm_generator::check(&config).await,
+    };
+    let report = match report {
+        Ok(report) => report,
         Err(err) => {
-            tracing::error!("Failed to load templates: {}", err);
-            return;
+            tracing::error!("{:#}", err);
+            std::process::exit(EXIT_FATAL);
         }
     };
 
-    let context = Arc::new(Context {
-        templates,
-        args,
-        md_post_list: OnceLock::new(),
-    });
-    let result = tokio::join!(
-        content::process_content(&context),
-        static_files::process_static(&context)
+    print_diagnostics(&report, args.message_format);
+
+    tracing::info!(
+        "Processed content with {} errors and {} warnings.",
+        report.result.errors().len(),
+        report.result.warnings().len()
     );
+    match command {
+        Command::Build => tracing::info!("Generated website in {:.2?}.", report.duration),
+        Command::Check => tracing::info!("Checked website in {:.2?}.", report.duration),
+    }
 
-    match result.0 {
-        Ok(result) => {
-            tracing::info!(
-                "Processed content with {} errors and {} warnings.",
-                result.errors().len(),
-                result.warnings().len()
-            );
+    if !report.is_success() || (args.deny_warnings && !report.result.warnings().is_empty()) {
+        std::process::exit(EXIT_BUILD_FAILED);
+    }
+}
 
-            for error in result.errors() {
-                tracing::error!("{}", error);
-            }
-            for warning in result.warnings() {
-                tracing::warn!("{}", warning);
-            }
-        }
-        Err(err) => {
-            tracing::error!("Failed to process content: {}", err);
-            return;
+fn print_diagnostics(report: &BuildReport, format: MessageFormat) {
+    for diagnostic in report.result.diagnostics() {
+        match format {
+            MessageFormat::Human => eprintln!("{}\n", diagnostic.render()),
+            MessageFormat::Json => println!("{}", diagnostic.to_json()),
         }
     }
-
-    tracing::info!("Generated website.")
 }
@@ -0,0 +1,197 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use walkdir::WalkDir;
+
+use crate::Context;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directories inside the output which are fully owned by the generator and can be pruned.
+const PRUNED_DIRECTORIES: [&str; 2] = ["content", "static"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Output files relative to the output directory
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub hash: String,
+    /// Source file relative to the project directory
+    pub source: Option<String>,
+}
+
+impl Manifest {
+    pub fn contains(&self, output: &Path, path: &Path) -> bool {
+        self.files.contains_key(&relative(output, path))
+    }
+
+    pub async fn save(&self, output: &Path) -> anyhow::Result<()> {
+        fs::write(output.join(MANIFEST_FILE), serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// Writes the file into the output directory and records it in the build manifest.
+pub async fn write(
+    context: &Context,
+    path: &Path,
+    data: &[u8],
+    source: Option<&Path>,
+) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    // The file may be a hard link shared with the currently served generation, so it must be
+    // replaced instead of overwritten.
+    match fs::remove_file(path).await {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error.into()),
+        _ => (),
+    }
+    fs::write(path, data).await?;
+
+    record(context, path, data, source);
+    Ok(())
+}
+
+/// Records a file which was produced by this build without writing it, e.g. because it is up to
+/// date.
+pub fn record(context: &Context, path: &Path, data: &[u8], source: Option<&Path>) {
+    let entry = ManifestEntry {
+        hash: format!("{:x}", Sha256::digest(data)),
+        source: source.map(|source| relative(&context.config.project, source)),
+    };
+
+    context
+        .manifest
+        .lock()
+        .unwrap()
+        .files
+        .insert(relative(&context.output, path), entry);
+}
+
+/// Removes files which were not produced by the build described by the manifest.
+pub fn prune(output: &Path, manifest: &Manifest) -> anyhow::Result<usize> {
+    let mut removed = 0;
+    for directory in PRUNED_DIRECTORIES {
+        let directory = output.join(directory);
+        if !directory.exists() {
+            continue;
+        }
+
+        for entry in WalkDir::new(&directory)
+            .contents_first(true)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            let path = entry.path();
+            if entry.file_type().is_dir() {
+                if path != directory && std::fs::read_dir(path)?.next().is_none() {
+                    std::fs::remove_dir(path)?;
+                }
+                continue;
+            }
+
+            if !manifest.contains(output, path) {
+                std::fs::remove_file(path)?;
+                removed += 1;
+                tracing::trace!("Removed stale output file '{}'.", path.display());
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+fn relative(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn manifest(files: &[&str]) -> Manifest {
+        Manifest {
+            files: files
+                .iter()
+                .map(|file| {
+                    (
+                        file.to_string(),
+                        ManifestEntry {
+                            hash: String::new(),
+                            source: None,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn create(output: &Path, file: &str) {
+        let path = output.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, file).unwrap();
+    }
+
+    #[test]
+    fn removes_stale_files() {
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path();
+        create(output, "content/index.html");
+        create(output, "content/old/page.html");
+        create(output, "static/old.css");
+
+        let removed = prune(output, &manifest(&["content/index.html"])).unwrap();
+        assert_eq!(removed, 2);
+        assert!(!output.join("content/old/page.html").exists());
+        assert!(!output.join("static/old.css").exists());
+        // Emptied directories are removed, the pruned directories themselves are kept.
+        assert!(!output.join("content/old").exists());
+        assert!(output.join("static").is_dir());
+    }
+
+    #[test]
+    fn keeps_files_in_manifest() {
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path();
+        create(output, "content/blog/post.html");
+        create(output, "static/style.css");
+
+        let manifest = manifest(&["content/blog/post.html", "static/style.css"]);
+        assert_eq!(prune(output, &manifest).unwrap(), 0);
+        assert!(output.join("content/blog/post.html").exists());
+        assert!(output.join("static/style.css").exists());
+        assert!(manifest.contains(output, &output.join("static/style.css")));
+    }
+
+    #[test]
+    fn ignores_files_outside_of_pruned_directories() {
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path();
+        create(output, MANIFEST_FILE);
+        create(output, "routes.json");
+        create(output, "search/index.bin");
+        create(output, "contents/page.html");
+
+        assert_eq!(prune(output, &manifest(&[])).unwrap(), 0);
+        for file in [
+            MANIFEST_FILE,
+            "routes.json",
+            "search/index.bin",
+            "contents/page.html",
+        ] {
+            assert!(output.join(file).exists(), "{}", file);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// File in the generation directory with routes served by `vsm` in addition to pages.
+pub const ROUTES_FILE: &str = "routes.json";
+
+/// Pages served by `vsm` with the matching status code, e.g. `content/404.md`.
+pub const ERROR_PAGES: [&str; 2] = ["404", "500"];
+/// Replaced by `vsm` with links to pages similar to the requested one.
+pub const SUGGESTIONS_PLACEHOLDER: &str = "<vsm-suggestions></vsm-suggestions>";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Routes {
+    /// Keyed by normalized paths, see [`normalize`]
+    pub redirects: BTreeMap<String, Redirect>,
+    /// Links of all pages except error pages
+    #[serde(default)]
+    pub pages: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redirect {
+    pub to: String,
+    pub status: u16,
+}
+
+impl Routes {
+    pub fn load(generation: &Path) -> anyhow::Result<Self> {
+        let path = generation.join(ROUTES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn redirect(&self, path: &str) -> Option<&Redirect> {
+        self.redirects.get(&normalize(path))
+    }
+}
+
+/// Converts the path into the form used by page links, without leading and trailing slashes.
+pub fn normalize(path: &str) -> String {
+    path.split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_matches('/')
+        .to_owned()
+}
+
+pub fn is_error_page(link: &str) -> bool {
+    ERROR_PAGES.contains(&link)
+}
@@ -5,31 +5,28 @@ use std::{
 };
 
 use flate2::{write::ZlibEncoder, Compression};
-use tokio::{
-    fs,
-    io::{AsyncReadExt, AsyncWriteExt},
-};
+use tokio::{fs, io::AsyncReadExt};
 use walkdir::WalkDir;
 
-use crate::Context;
+use crate::{content::ContentResult, output, Context};
 
-pub async fn process_static(context: &Arc<Context>) {
+pub async fn process_static(context: &Arc<Context>) -> ContentResult {
     let tasks: Vec<_> =
-        collect_files_for_processing(&Path::new(&context.args.project).join("static"))
+        collect_files_for_processing(&Path::new(&context.config.project).join("static"))
             .into_iter()
             .map(|arg| tokio::spawn(process_file(context.clone(), arg)))
             .collect();
 
-    let mut error = false;
+    let mut result = ContentResult::new();
     for task in tasks {
-        if task.await.unwrap().is_err() {
-            error = true;
+        match task.await {
+            Ok(Ok(())) => (),
+            Ok(Err(error)) => result.push_error(error),
+            Err(error) => result.push_error(error.into()),
         }
     }
 
-    if error {
-        tracing::error!("Error in processing static files.")
-    }
+    result
 }
 
 fn collect_files_for_processing(path: &Path) -> Vec<PathBuf> {
@@ -65,37 +62,27 @@ async fn process_file(context: Arc<Context>, path: PathBuf) -> anyhow::Result<()
         .await
         .expect("Unable to read file.");
 
-    let mut output_path = Path::new(&context.args.output).join(
-        path.strip_prefix(&context.args.project)
+    let mut output_path = context.output.join(
+        path.strip_prefix(&context.config.project)
             .expect("Unable to strip prefix."),
     );
 
-    #[cfg(not(debug_assertions))]
-    {
-        if path
+    if context.config.build.minify
+        && path
             .extension()
-            .map_or(false, |ext| ext == "css" || ext == "js")
-        {
-            buffer = minify_html::minify(
-                buffer.as_slice(),
-                &minify_html::Cfg {
-                    minify_css: true,
-                    minify_js: true,
-                    ..minify_html::Cfg::spec_compliant()
-                },
-            );
-        }
+            .is_some_and(|ext| ext == "css" || ext == "js")
+    {
+        buffer = minify_html::minify(
+            buffer.as_slice(),
+            &minify_html::Cfg {
+                minify_css: true,
+                minify_js: true,
+                ..minify_html::Cfg::spec_compliant()
+            },
+        );
     }
 
-    fs::create_dir_all(output_path.parent().unwrap())
-        .await
-        .expect("Unable to create directory.");
-    fs::File::create(&output_path)
-        .await
-        .expect("Unable to create file.")
-        .write_all(buffer.as_slice())
-        .await
-        .expect("Unable to write file.");
+    output::write(&context, &output_path, &buffer, Some(&path)).await?;
 
     let extension = path.extension().map_or("", |ext| ext.to_str().unwrap());
     if extension == "png" || extension == "jpg" || extension == "jpeg" || extension == "webp" {
@@ -107,10 +94,7 @@ async fn process_file(context: Arc<Context>, path: PathBuf) -> anyhow::Result<()
     encoder.write_all(buffer.as_slice())?;
 
     output_path.set_extension(format!("{extension}.deflate"));
-    fs::File::create(output_path)
-        .await?
-        .write_all(encoder.finish()?)
-        .await?;
+    output::write(&context, &output_path, encoder.finish()?, Some(&path)).await?;
 
     Ok(())
 }
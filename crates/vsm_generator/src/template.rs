@@ -1,10 +1,14 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use walkdir::DirEntry;
 
 #[derive(Debug)]
 pub struct Template {
     pub name: String,
+    pub path: PathBuf,
     pub data: String,
 }
 
@@ -16,6 +20,7 @@ impl Template {
                 .to_str()
                 .unwrap()
                 .to_owned(),
+            path: file.path().to_path_buf(),
             data: fs::read_to_string(file.path()).unwrap(),
         })
     }
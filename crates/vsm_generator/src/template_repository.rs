@@ -30,4 +30,15 @@ impl TemplateRepository {
     pub fn get(&self, name: &str) -> Option<&Template> {
         self.templates.get(name)
     }
+
+    /// Paths of all templates, sorted so diagnostics are located the same way between runs.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        let mut paths = self
+            .templates
+            .values()
+            .map(|template| template.path.as_path())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.into_iter()
+    }
 }
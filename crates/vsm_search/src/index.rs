@@ -0,0 +1,349 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{tokenizer, Language};
+
+const TITLE_WEIGHT: u32 = 3;
+const DESCRIPTION_WEIGHT: u32 = 2;
+const TEXT_WEIGHT: u32 = 1;
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+const SNIPPET_BEFORE: usize = 60;
+const SNIPPET_LENGTH: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Document {
+    pub link: String,
+    pub title: String,
+    pub description: String,
+    pub text: String,
+    pub language: Language,
+    length: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Posting {
+    document: u32,
+    weight: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    terms: HashMap<String, Vec<Posting>>,
+}
+
+#[derive(Debug)]
+pub struct SearchResult<'a> {
+    pub document: &'a Document,
+    pub score: f64,
+    pub snippet: String,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let buffer = fs::read(path)?;
+        let reader = flexbuffers::Reader::get_root(buffer.as_slice())?;
+        Ok(Self::deserialize(reader)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let data = self.to_bytes()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)?;
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut serializer = flexbuffers::FlexbufferSerializer::new();
+        self.serialize(&mut serializer)?;
+        Ok(serializer.take_buffer())
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn add(&mut self, link: String, title: String, description: String, text: String) {
+        let language = Language::detect(&text);
+        let document = self.documents.len() as u32;
+
+        let mut weights = HashMap::<String, u32>::new();
+        let mut length = 0;
+        for (field, weight) in [
+            (&title, TITLE_WEIGHT),
+            (&description, DESCRIPTION_WEIGHT),
+            (&text, TEXT_WEIGHT),
+        ] {
+            for token in tokenizer::tokenize(field) {
+                *weights
+                    .entry(language.stem(&token.normalized()))
+                    .or_default() += weight;
+                length += 1;
+            }
+        }
+
+        for (term, weight) in weights {
+            self.terms
+                .entry(term)
+                .or_default()
+                .push(Posting { document, weight });
+        }
+
+        self.documents.push(Document {
+            link,
+            title,
+            description,
+            text,
+            language,
+            length,
+        });
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult<'_>> {
+        if self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let average_length = self
+            .documents
+            .iter()
+            .map(|document| document.length as f64)
+            .sum::<f64>()
+            / self.documents.len() as f64;
+
+        let mut scores = HashMap::<u32, f64>::new();
+        let mut query_terms = HashSet::new();
+        for token in tokenizer::tokenize(query) {
+            let word = token.normalized();
+
+            let mut word_scores = HashMap::<u32, f64>::new();
+            for term in query_variants(&word) {
+                let Some(postings) = self.terms.get(&term) else {
+                    continue;
+                };
+                query_terms.insert(term);
+
+                let idf = ((self.documents.len() as f64 - postings.len() as f64 + 0.5)
+                    / (postings.len() as f64 + 0.5)
+                    + 1.0)
+                    .ln();
+                for posting in postings {
+                    let length = self.documents[posting.document as usize].length as f64;
+                    let frequency = posting.weight as f64;
+                    let score = idf * (frequency * (BM25_K1 + 1.0))
+                        / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length));
+
+                    let entry = word_scores.entry(posting.document).or_default();
+                    *entry = entry.max(score);
+                }
+            }
+
+            for (document, score) in word_scores {
+                *scores.entry(document).or_default() += score;
+            }
+        }
+
+        let mut scores = scores.into_iter().collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores.truncate(limit);
+
+        scores
+            .into_iter()
+            .map(|(document, score)| {
+                let document = &self.documents[document as usize];
+                SearchResult {
+                    document,
+                    score,
+                    snippet: create_snippet(document, &query_terms),
+                }
+            })
+            .collect()
+    }
+}
+
+fn query_variants(word: &str) -> HashSet<String> {
+    let mut variants = Language::ALL
+        .iter()
+        .map(|language| language.stem(word))
+        .collect::<HashSet<_>>();
+    variants.insert(word.to_owned());
+    variants
+}
+
+fn create_snippet(document: &Document, query_terms: &HashSet<String>) -> String {
+    let text = match document.text.trim().is_empty() {
+        true => &document.description,
+        false => &document.text,
+    };
+
+    let matches = tokenizer::tokenize(text)
+        .filter(|token| query_terms.contains(&document.language.stem(&token.normalized())))
+        .map(|token| (token.start, token.end))
+        .collect::<Vec<_>>();
+
+    let first = matches.first().map_or(0, |(start, _)| *start);
+    let start = floor_char_boundary(text, first.saturating_sub(SNIPPET_BEFORE));
+    let start = match start == 0 {
+        true => 0,
+        false => text[start..]
+            .find(' ')
+            .map_or(start, |space| start + space + 1),
+    };
+    let end = floor_char_boundary(text, start + SNIPPET_LENGTH);
+    let end = match end == text.len() {
+        true => end,
+        false => text[..end].rfind(' ').filter(|e| *e > start).unwrap_or(end),
+    };
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+
+    let mut position = start;
+    for (match_start, match_end) in matches {
+        if match_start < position || match_end > end {
+            continue;
+        }
+
+        snippet.push_str(&escape_html(&text[position..match_start]));
+        snippet.push_str("<mark>");
+        snippet.push_str(&escape_html(&text[match_start..match_end]));
+        snippet.push_str("</mark>");
+        position = match_end;
+    }
+    snippet.push_str(&escape_html(&text[position..end]));
+
+    if end < text.len() {
+        snippet.push('…');
+    }
+
+    snippet
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.add(
+            "rust".to_owned(),
+            "Rust ownership".to_owned(),
+            "Memory without a garbage collector".to_owned(),
+            "The borrow checker validates references at compile time.".to_owned(),
+        );
+        index.add(
+            "web".to_owned(),
+            "Web servers".to_owned(),
+            "Serving pages".to_owned(),
+            "A server written in Rust handles requests with the async runtime.".to_owned(),
+        );
+        index.add(
+            "polish".to_owned(),
+            "Programowanie w języku Rust".to_owned(),
+            "Krótki wpis".to_owned(),
+            "To jest wpis o programowaniu i bezpiecznym kodzie.".to_owned(),
+        );
+        index
+    }
+
+    #[test]
+    fn ranks_title_matches_higher() {
+        let index = index();
+        let results = index.search("ownership", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.link, "rust");
+
+        let results = index.search("rust", 10);
+        assert_eq!(results.len(), 3);
+        assert_ne!(results[0].document.link, "web");
+        assert!(results.windows(2).all(|r| r[0].score >= r[1].score));
+    }
+
+    #[test]
+    fn sums_scores_of_query_words() {
+        let index = index();
+        let results = index.search("rust server", 10);
+        assert_eq!(results[0].document.link, "web");
+    }
+
+    #[test]
+    fn matches_inflected_words() {
+        let index = index();
+        let results = index.search("programowania", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.link, "polish");
+        assert_eq!(results[0].document.language, Language::Polish);
+    }
+
+    #[test]
+    fn limits_results() {
+        assert_eq!(index().search("rust", 2).len(), 2);
+        assert!(index().search("missing", 10).is_empty());
+        assert!(SearchIndex::new().search("rust", 10).is_empty());
+    }
+
+    #[test]
+    fn marks_matches_in_snippet() {
+        let mut index = SearchIndex::new();
+        index.add(
+            "a".to_owned(),
+            "Title".to_owned(),
+            String::new(),
+            "Compare <b> & borrow values.".to_owned(),
+        );
+        let results = index.search("borrow", 10);
+        assert_eq!(
+            results[0].snippet,
+            "Compare &lt;b&gt; &amp; <mark>borrow</mark> values."
+        );
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let bytes = index().to_bytes().unwrap();
+        let reader = flexbuffers::Reader::get_root(bytes.as_slice()).unwrap();
+        let index = SearchIndex::deserialize(reader).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.search("ownership", 10)[0].document.link, "rust");
+    }
+}
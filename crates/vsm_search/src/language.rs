@@ -0,0 +1,53 @@
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+
+use crate::{polish_stemmer, tokenizer};
+
+const POLISH_STOP_WORDS: &[&str] = &[
+    "i", "w", "z", "na", "do", "nie", "się", "jest", "że", "to", "jak", "oraz", "czy", "ale",
+    "dla", "od", "po", "jeśli", "który", "która", "które", "są", "tak", "także",
+];
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "the", "and", "is", "of", "to", "in", "that", "it", "with", "for", "as", "on", "are", "this",
+    "be", "by", "or", "an", "if", "which", "can", "not", "from", "at",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    Polish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Polish];
+
+    pub fn detect(text: &str) -> Self {
+        let mut polish = 0;
+        let mut english = 0;
+
+        for token in tokenizer::tokenize(text) {
+            let word = token.normalized();
+            if word.chars().any(|c| "ąćęłńóśźż".contains(c)) {
+                polish += 1;
+            }
+
+            if POLISH_STOP_WORDS.contains(&word.as_str()) {
+                polish += 1;
+            } else if ENGLISH_STOP_WORDS.contains(&word.as_str()) {
+                english += 1;
+            }
+        }
+
+        match polish > english {
+            true => Language::Polish,
+            false => Language::English,
+        }
+    }
+
+    pub fn stem(self, word: &str) -> String {
+        match self {
+            Language::English => Stemmer::create(Algorithm::English).stem(word).into_owned(),
+            Language::Polish => polish_stemmer::stem(word),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod index;
+pub mod language;
+pub mod polish_stemmer;
+pub mod tokenizer;
+
+pub use index::{Document, SearchIndex, SearchResult};
+pub use language::Language;
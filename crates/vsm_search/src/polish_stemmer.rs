@@ -0,0 +1,43 @@
+const MIN_STEM_LENGTH: usize = 3;
+
+// Light stemmer, only strips the most common inflectional suffixes. Longest suffixes go first.
+const SUFFIXES: &[&str] = &[
+    "owaniach", "owaniami", "ościami", "owaniem", "ościach", "owaniom", "owania", "owanie",
+    "iejszy", "owałam", "owałem", "owaniu", "eniach", "eniami", "ościom", "ujemy", "ością",
+    "owego", "owała", "owało", "owali", "owały", "ejszy", "owych", "eniem", "ować", "ości", "owej",
+    "owym", "ował", "ując", "enia", "enie", "eniu", "iami", "iach", "owie", "ach", "ami", "ego",
+    "emu", "ymi", "imi", "ych", "ich", "owi", "uje", "ują", "ała", "ało", "ali", "ały", "ość",
+    "om", "ów", "ie", "ią", "ię", "ej", "ym", "im", "ał", "ić", "yć", "ać", "eć", "a", "e", "i",
+    "o", "u", "y", "ą", "ę",
+];
+
+pub fn stem(word: &str) -> String {
+    for suffix in SUFFIXES {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.chars().count() >= MIN_STEM_LENGTH {
+                return stem.to_owned();
+            }
+        }
+    }
+
+    word.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_longest_suffix() {
+        assert_eq!(stem("programowanie"), "program");
+        assert_eq!(stem("programowania"), "program");
+        assert_eq!(stem("możliwościami"), "możliw");
+    }
+
+    #[test]
+    fn keeps_minimal_stem_length() {
+        assert_eq!(stem("kotami"), "kot");
+        assert_eq!(stem("ala"), "ala");
+        assert_eq!(stem("rust"), "rust");
+    }
+}
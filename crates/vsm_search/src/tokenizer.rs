@@ -0,0 +1,37 @@
+pub struct Token<'a> {
+    pub start: usize,
+    pub end: usize,
+    pub text: &'a str,
+}
+
+impl Token<'_> {
+    pub fn normalized(&self) -> String {
+        self.text.to_lowercase()
+    }
+}
+
+pub fn tokenize(text: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.peek().is_some_and(|(_, c)| !c.is_alphanumeric()) {
+            chars.next();
+        }
+
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = chars.peek() {
+            if !c.is_alphanumeric() {
+                break;
+            }
+
+            end = i + c.len_utf8();
+            chars.next();
+        }
+
+        Some(Token {
+            start,
+            end,
+            text: &text[start..end],
+        })
+    })
+}
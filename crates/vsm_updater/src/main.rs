@@ -47,7 +47,7 @@ async fn get_latest_release(client: &Client) -> String {
         .await
         .expect("Failed to get releases string");
 
-    releases.sort_by(|a, b| a.published_at.cmp(&b.published_at));
+    releases.sort_by_key(|a| a.published_at);
     let release = releases.pop().expect("No releases found");
 
     tracing::info!("Latest release: {}", &release.tag_name);